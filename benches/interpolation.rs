@@ -0,0 +1,241 @@
+use std::time::{Duration, Instant};
+
+use bevy::{ecs::schedule::Schedule, prelude::*};
+use bevy_replicon::{
+    client::confirm_history::ConfirmHistory,
+    core::{replicon_server::RepliconServer, replicon_tick::RepliconTick},
+    prelude::{ChannelKind, ClientId, FromClient, RepliconPlugins},
+    server::server_tick::ServerTick,
+};
+use bevy_replicon_snap::{
+    interpolation::{
+        snapshot_interpolation_system, ExtrapolationMode, Interpolated, InterpolationCurve,
+        SnapshotBuffer, SnapshotInterpolationConfig,
+    },
+    prediction::{
+        drain_predicted_inputs_system, predicted_replay_system, rollback_component_system,
+        rollback_context_system, server_update_system, AppPredictionExt, NoContext, Predict,
+        PredictInfo, PredictSimple, Predicted, PredictedEventConfig, PredictedEventHistory,
+        PredictedInput, Reconciled, ServerInputQueue,
+    },
+    NetworkOwner, SnapshotInterpolationPlugin,
+};
+use bevy_replicon_snap_macros::Interpolate;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use serde::{Deserialize, Serialize};
+
+#[derive(Component, Deserialize, Serialize, Interpolate, Clone)]
+struct Position(Vec2);
+
+#[derive(Event, Deserialize, Serialize, Clone, Debug)]
+struct MoveDirection(Vec2);
+
+impl PredictSimple<MoveDirection> for Position {
+    fn apply_event(&mut self, event: &MoveDirection, info: &PredictInfo) {
+        self.0 += event.0 * info.delta_time * 100.0;
+    }
+}
+
+fn snapshot_insert(c: &mut Criterion) {
+    c.bench_function("snapshot_buffer_insert_1000", |b| {
+        b.iter(|| {
+            let mut buffer = SnapshotBuffer::new();
+            for tick in 0..1000 {
+                buffer.insert(Position(Vec2::splat(tick as f32)), tick);
+            }
+            black_box(buffer.latest_snapshot_tick())
+        })
+    });
+}
+
+fn interpolation_system(c: &mut Criterion) {
+    let mut world = World::new();
+    world.insert_resource(Time::<()>::default());
+    world.insert_resource(SnapshotInterpolationConfig {
+        max_tick_rate: 30,
+        curve: InterpolationCurve::default(),
+        max_snapshot_age_secs: 1.0,
+        max_snapshots: 2,
+        extrapolation: ExtrapolationMode::Disabled,
+    });
+    for index in 0..1000 {
+        let mut buffer = SnapshotBuffer::new();
+        buffer.insert(Position(Vec2::ZERO), 0);
+        buffer.insert(Position(Vec2::splat(index as f32)), 1);
+        world.spawn((Position(Vec2::ZERO), buffer, Interpolated));
+    }
+    let mut schedule = Schedule::default();
+    schedule.add_systems(snapshot_interpolation_system::<Position>);
+
+    c.bench_function("snapshot_interpolation_system_1000_entities", |b| {
+        b.iter(|| schedule.run(&mut world))
+    });
+}
+
+fn prediction_replay(c: &mut Criterion) {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        RepliconPlugins,
+        SnapshotInterpolationPlugin::new(60),
+    ))
+    .add_client_predicted_event::<MoveDirection>(ChannelKind::Ordered)
+    .predict_event_for_component_simple::<MoveDirection, Position>();
+    let world = app.world_mut();
+    let mut history = world.resource_mut::<PredictedEventHistory<MoveDirection>>();
+    for frame in 0..64 {
+        history.insert(
+            MoveDirection(Vec2::X),
+            RepliconTick::new(0),
+            frame,
+            1.0 / 60.0,
+        );
+    }
+    let mut buffer = SnapshotBuffer::new();
+    buffer.insert(Position(Vec2::ZERO), 0);
+    let entity = world
+        .spawn((
+            Position(Vec2::ZERO),
+            buffer,
+            NoContext,
+            Predicted,
+            ConfirmHistory::new(RepliconTick::new(0)),
+        ))
+        .id();
+    let mut schedule = Schedule::default();
+    schedule.add_systems(
+        (
+            rollback_component_system::<Position>,
+            rollback_context_system::<NoContext>,
+            predicted_replay_system,
+        )
+            .chain(),
+    );
+
+    c.bench_function("prediction_replay_64_events", |b| {
+        b.iter_custom(|iters| {
+            let mut elapsed = Duration::ZERO;
+            for _ in 0..iters {
+                // Rolls back to the same confirmed tick and replays all events again
+                world.entity_mut(entity).remove::<Reconciled>();
+                let start = Instant::now();
+                schedule.run(world);
+                elapsed += start.elapsed();
+            }
+            elapsed
+        })
+    });
+}
+
+const CLIENTS: u64 = 64;
+const OWNED_ENTITIES: u64 = 500;
+
+/// Dispatch before inputs were indexed by owner, scanning all entities for every input.
+fn scanning_server_update_system(
+    queue: Res<ServerInputQueue<MoveDirection>>,
+    mut subjects: Query<(&NetworkOwner, &mut Position, &mut NoContext)>,
+) {
+    for (client_id, input) in queue.ready() {
+        for (owner, mut position, mut context) in &mut subjects {
+            if client_id.get() == owner.0 {
+                Predict::apply_event(
+                    &mut *position,
+                    &input.event,
+                    &input.info(1.0 / 30.0),
+                    &mut *context,
+                );
+            }
+        }
+    }
+}
+
+fn server_update_world() -> World {
+    let mut world = World::new();
+    world.insert_resource(RepliconServer::default());
+    world.insert_resource(ServerTick::default());
+    world.insert_resource(SnapshotInterpolationConfig {
+        max_tick_rate: 30,
+        curve: InterpolationCurve::default(),
+        max_snapshot_age_secs: 1.0,
+        max_snapshots: 2,
+        extrapolation: ExtrapolationMode::Disabled,
+    });
+    world.insert_resource(ServerInputQueue::<MoveDirection>::new(
+        PredictedEventConfig::default(),
+    ));
+    world.init_resource::<Events<FromClient<MoveDirection>>>();
+    for index in 0..CLIENTS * OWNED_ENTITIES {
+        world.spawn((
+            Position(Vec2::ZERO),
+            NoContext,
+            NetworkOwner(index % CLIENTS),
+        ));
+    }
+    world
+}
+
+fn queue_inputs(world: &mut World, sequence: u32) {
+    let mut queue = world.resource_mut::<ServerInputQueue<MoveDirection>>();
+    for client in 0..CLIENTS {
+        queue.push(
+            ClientId::new(client),
+            PredictedInput {
+                event: MoveDirection(Vec2::X),
+                tick: RepliconTick::new(0),
+                delta_time: 1.0 / 30.0,
+                sequence,
+            },
+        );
+    }
+}
+
+fn server_update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("server_update_64_clients_500_entities");
+
+    let mut world = server_update_world();
+    let mut schedule = Schedule::default();
+    schedule.add_systems(
+        (
+            drain_predicted_inputs_system::<MoveDirection>,
+            scanning_server_update_system,
+        )
+            .chain(),
+    );
+    let mut sequence = 0;
+    group.bench_function("scanning", |b| {
+        b.iter(|| {
+            queue_inputs(&mut world, sequence);
+            sequence += 1;
+            schedule.run(&mut world)
+        })
+    });
+
+    let mut world = server_update_world();
+    let mut schedule = Schedule::default();
+    schedule.add_systems(
+        (
+            drain_predicted_inputs_system::<MoveDirection>,
+            server_update_system::<MoveDirection, NoContext, Position>,
+        )
+            .chain(),
+    );
+    let mut sequence = 0;
+    group.bench_function("indexed", |b| {
+        b.iter(|| {
+            queue_inputs(&mut world, sequence);
+            sequence += 1;
+            schedule.run(&mut world)
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    snapshot_insert,
+    interpolation_system,
+    prediction_replay,
+    server_update
+);
+criterion_main!(benches);
@@ -44,9 +44,7 @@ fn main() {
                 ..default()
             }),
             RepliconRenetPlugins,
-            SnapshotInterpolationPlugin {
-                max_tick_rate: MAX_TICK_RATE,
-            },
+            SnapshotInterpolationPlugin::new(MAX_TICK_RATE),
             SimpleBoxPlugin,
         ))
         .run();
@@ -21,7 +21,8 @@ use bevy_replicon_renet::{
 use bevy_replicon_snap::{
     interpolation::AppInterpolationExt,
     prediction::OwnerPredicted,
-    prediction::{AppPredictionExt, Predict},
+    prediction::{AppPredictionExt, Predict, PredictInfo, Predicted},
+    spawn::{AppPredictedSpawnExt, PredictSpawn},
     NetworkOwner, SnapshotInterpolationPlugin,
 };
 use bevy_replicon_snap_macros::Interpolate;
@@ -47,9 +48,7 @@ fn main() {
                 ..default()
             }),
             RepliconRenetPlugins,
-            SnapshotInterpolationPlugin {
-                max_tick_rate: MAX_TICK_RATE,
-            },
+            SnapshotInterpolationPlugin::new(MAX_TICK_RATE),
             SimpleBoxPlugin,
         ))
         .run();
@@ -63,6 +62,8 @@ impl Plugin for SimpleBoxPlugin {
             .replicate::<PlayerColor>()
             .add_client_predicted_event::<MoveDirection>(ChannelKind::Ordered)
             .predict_event_for_component::<MoveDirection, MovementSystemContext, PlayerPosition>()
+            .replicate::<Projectile>()
+            .add_predicted_spawn_event::<Shoot>(ChannelKind::Ordered)
             .add_systems(
                 Startup,
                 (Self::cli_system.map(Result::unwrap), Self::init_system),
@@ -71,7 +72,13 @@ impl Plugin for SimpleBoxPlugin {
                 Update,
                 (
                     Self::server_event_system.run_if(resource_exists::<RenetServer>), // Runs only on the server.
-                    (Self::draw_boxes_system, Self::input_system),
+                    (
+                        Self::draw_boxes_system,
+                        Self::draw_projectiles_system,
+                        Self::input_system,
+                        Self::shoot_system,
+                        Self::projectile_movement_system,
+                    ),
                 ),
             );
     }
@@ -205,6 +212,46 @@ impl SimpleBoxPlugin {
         }
     }
 
+    fn draw_projectiles_system(
+        mut gizmos: Gizmos,
+        projectiles: Query<&PlayerPosition, With<Projectile>>,
+    ) {
+        for position in &projectiles {
+            gizmos.circle_2d(Isometry2d::from_translation(position.0), 10.0, Color::WHITE);
+        }
+    }
+
+    /// Shoots a projectile from the local player when space is pressed.
+    /// The client spawns it immediately and keeps it once the server projectile replicates.
+    fn shoot_system(
+        mut shoot_events: EventWriter<Shoot>,
+        input: Res<ButtonInput<KeyCode>>,
+        players: Query<&PlayerPosition, With<Predicted>>,
+    ) {
+        if input.just_pressed(KeyCode::Space) {
+            let origin = players
+                .get_single()
+                .map_or(Vec2::ZERO, |position| position.0);
+            shoot_events.send(Shoot { origin });
+        }
+    }
+
+    /// Moves projectiles and despawns them on the server once their lifetime ran out.
+    fn projectile_movement_system(
+        mut commands: Commands,
+        time: Res<Time>,
+        server: Res<RepliconServer>,
+        mut projectiles: Query<(Entity, &mut PlayerPosition, &mut Projectile)>,
+    ) {
+        for (entity, mut position, mut projectile) in &mut projectiles {
+            position.0 += projectile.velocity * time.delta_secs();
+            projectile.lifetime -= time.delta_secs();
+            if projectile.lifetime <= 0.0 && server.is_running() {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+
     /// Reads player inputs and sends [`MoveCommandEvents`]
     fn input_system(mut move_events: EventWriter<MoveDirection>, input: Res<ButtonInput<KeyCode>>) {
         let mut direction = Vec2::ZERO;
@@ -226,7 +273,7 @@ impl SimpleBoxPlugin {
     }
 }
 
-#[derive(Component, Serialize, Deserialize)]
+#[derive(Component, Serialize, Deserialize, Clone)]
 struct MovementSystemContext {
     pub move_speed: f32,
 }
@@ -235,10 +282,10 @@ impl Predict<MoveDirection, MovementSystemContext> for PlayerPosition {
     fn apply_event(
         &mut self,
         event: &MoveDirection,
-        delta_time: f32,
-        context: &MovementSystemContext,
+        info: &PredictInfo,
+        context: &mut MovementSystemContext,
     ) {
-        self.0 += event.0 * delta_time * context.move_speed;
+        self.0 += event.0 * info.delta_time * context.move_speed;
     }
 }
 
@@ -296,6 +343,33 @@ struct PlayerPosition(Vec2);
 #[derive(Component, Deserialize, Serialize)]
 struct PlayerColor(Color);
 
+#[derive(Component, Deserialize, Serialize)]
+struct Projectile {
+    velocity: Vec2,
+    lifetime: f32,
+}
+
+/// Spawns a projectile, predicted by the shooting client.
+#[derive(Debug, Deserialize, Event, Serialize, Clone)]
+struct Shoot {
+    origin: Vec2,
+}
+
+impl PredictSpawn for Shoot {
+    fn spawn(&self, commands: &mut Commands) -> Entity {
+        commands
+            .spawn((
+                PlayerPosition(self.origin),
+                Projectile {
+                    velocity: Vec2::Y * 400.0,
+                    lifetime: 2.0,
+                },
+                Replicated,
+            ))
+            .id()
+    }
+}
+
 /// A movement event for the controlled box.
 #[derive(Debug, Default, Deserialize, Event, Serialize, Clone)]
 struct MoveDirection(Vec2);
@@ -3,38 +3,173 @@ extern crate proc_macro;
 use proc_macro::TokenStream;
 
 use quote::quote;
-use syn::DeriveInput;
-use syn::{parse_macro_input, Data, DataStruct, Fields};
+use syn::{
+    parse_macro_input, Data, DataStruct, DeriveInput, Error, Field, Fields, Index, LitStr, Member,
+    Path, Result,
+};
 
-#[proc_macro_derive(Interpolate)]
+/// How a field is derived from the two values.
+enum FieldMode {
+    Interpolate,
+    /// Copied from `self`, `#[interpolate(skip)]`.
+    Skip,
+    /// Taken from `other`, `#[interpolate(skip_to_other)]`.
+    SkipToOther,
+    /// Blended by a `fn(&T, &T, f32) -> T`, `#[interpolate(with = "path")]`.
+    With(Path),
+}
+
+fn field_mode(field: &Field) -> Result<FieldMode> {
+    let mut mode = FieldMode::Interpolate;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("interpolate"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                mode = FieldMode::Skip;
+                Ok(())
+            } else if meta.path.is_ident("skip_to_other") {
+                mode = FieldMode::SkipToOther;
+                Ok(())
+            } else if meta.path.is_ident("with") {
+                let path: LitStr = meta.value()?.parse()?;
+                mode = FieldMode::With(path.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `skip`, `skip_to_other` or `with`"))
+            }
+        })?;
+    }
+    Ok(mode)
+}
+
+/// Interpolates all fields of a struct, fields marked with `#[interpolate(skip)]` keep the value
+/// of `self` and fields marked with `#[interpolate(skip_to_other)]` take the value of `other`.
+/// Fields marked with `#[interpolate(with = "path")]` are blended by the function at `path`
+/// with the signature `fn(&T, &T, f32) -> T`.
+///
+/// `distance` is the euclidean combination of the distances of all interpolated fields,
+/// skipped fields and fields with a custom blend function don't contribute to it.
+#[proc_macro_derive(Interpolate, attributes(interpolate))]
 pub fn derive_interpolate(input: TokenStream) -> TokenStream {
-    let DeriveInput { ident, data, .. } = parse_macro_input!(input);
+    let DeriveInput {
+        ident,
+        generics,
+        data,
+        ..
+    } = parse_macro_input!(input);
 
-    let body = match data {
+    let (fields, named) = match data {
         Data::Struct(DataStruct {
             fields: Fields::Named(fields),
             ..
-        }) => {
-            let field_name = fields.named.iter().map(|field| &field.ident);
-            quote! {
-                Self {
-                    #(
-                        #field_name: self.#field_name.lerp(other.value, t),
-                    )*
-                }
-            }
-        }
+        }) => (fields.named, true),
         Data::Struct(DataStruct {
-            fields: Fields::Unnamed(_),
+            fields: Fields::Unnamed(fields),
             ..
-        }) => quote! { Self(self.0.lerp(other.0, t)) },
-        _ => panic!("expected a struct"),
+        }) => (fields.unnamed, false),
+        Data::Struct(_) => {
+            return Error::new_spanned(&ident, "`Interpolate` can't be derived for unit structs")
+                .to_compile_error()
+                .into()
+        }
+        Data::Enum(data) => {
+            return Error::new_spanned(
+                data.enum_token,
+                "`Interpolate` can only be derived for structs",
+            )
+            .to_compile_error()
+            .into()
+        }
+        Data::Union(data) => {
+            return Error::new_spanned(
+                data.union_token,
+                "`Interpolate` can only be derived for structs",
+            )
+            .to_compile_error()
+            .into()
+        }
+    };
+
+    let mut values = Vec::new();
+    let mut distances = Vec::new();
+    let mut bounds = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(index)),
+        };
+        let ty = &field.ty;
+        let mode = match field_mode(field) {
+            Ok(mode) => mode,
+            Err(error) => return error.to_compile_error().into(),
+        };
+        match mode {
+            FieldMode::Interpolate => {
+                values.push(quote! {
+                    bevy_replicon_snap::interpolation::Interpolate::interpolate(
+                        &self.#member,
+                        other.#member,
+                        t,
+                    )
+                });
+                distances.push(quote! {
+                    bevy_replicon_snap::interpolation::Interpolate::distance(
+                        &self.#member,
+                        &other.#member,
+                    )
+                });
+                bounds.push(quote! { #ty: bevy_replicon_snap::interpolation::Interpolate });
+            }
+            FieldMode::Skip => {
+                values.push(quote! { ::core::clone::Clone::clone(&self.#member) });
+                bounds.push(quote! { #ty: ::core::clone::Clone });
+            }
+            FieldMode::SkipToOther => values.push(quote! { other.#member }),
+            FieldMode::With(path) => {
+                values.push(quote! { #path(&self.#member, &other.#member, t) })
+            }
+        }
+    }
+    let body = if named {
+        let field_name = fields.iter().map(|field| &field.ident);
+        quote! {
+            Self {
+                #(#field_name: #values,)*
+            }
+        }
+    } else {
+        quote! {
+            Self(
+                #(#values,)*
+            )
+        }
     };
+
+    // Bounds on the field types report missing implementations at the derive
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let predicates = where_clause
+        .into_iter()
+        .flat_map(|where_clause| &where_clause.predicates);
     let output = quote! {
-        impl bevy_replicon_snap::interpolation::Interpolate for #ident {
+        impl #impl_generics bevy_replicon_snap::interpolation::Interpolate for #ident #ty_generics
+        where
+            #(#predicates,)*
+            #(#bounds,)*
+        {
             fn interpolate(&self, other: Self, t: f32) -> Self {
               #body
             }
+
+            fn distance(&self, other: &Self) -> f32 {
+                let squared: f32 = 0.0 #(+ {
+                    let distance: f32 = #distances;
+                    distance * distance
+                })*;
+                squared.sqrt()
+            }
         }
     };
     output.into()
@@ -0,0 +1,151 @@
+use std::any::type_name;
+
+use bevy::{
+    app::{App, Update},
+    ecs::{
+        component::Component,
+        entity::{Entity, EntityMapper, MapEntities},
+        event::{Event, EventReader, EventWriter},
+        query::{Changed, Without},
+        schedule::IntoSystemConfigs,
+        system::{Query, Res, Resource},
+    },
+    log::debug,
+};
+use bevy_replicon::{
+    core::replicon_client::RepliconClient,
+    prelude::{
+        client_connected, server_or_singleplayer, ChannelKind, ClientEventAppExt, FromClient,
+    },
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    interpolation::{AppInterpolationExt, Interpolate, Interpolated},
+    prediction::{local_client_id, LocalClientId},
+    NetworkOwner,
+};
+
+/// Sent by the owning client whenever its client authoritative `C` changed.
+#[derive(Event, Serialize, Deserialize, Clone, Debug)]
+pub struct ClientAuthoritativeUpdate<C> {
+    pub entity: Entity,
+    pub value: C,
+}
+
+impl<C> MapEntities for ClientAuthoritativeUpdate<C> {
+    fn map_entities<M: EntityMapper>(&mut self, entity_mapper: &mut M) {
+        self.entity = entity_mapper.map_entity(self.entity);
+    }
+}
+
+/// Server side validation of client authoritative values of `C`, registered with
+/// [`AppAuthorityExt::replicate_client_authoritative_with_validation`].
+///
+/// Receives the current and the proposed value, returns the value to apply or `None` to reject it.
+#[derive(Resource)]
+pub struct AuthorityValidation<C>(pub fn(&C, &C) -> Option<C>);
+
+/// Sends local changes of `C` on entities owned by this client to the server.
+pub fn send_client_authoritative_system<C: Component + Clone>(
+    q_changed: Query<(Entity, &NetworkOwner, &C), (Changed<C>, Without<Interpolated>)>,
+    local_client_id_res: Option<Res<LocalClientId>>,
+    client: Res<RepliconClient>,
+    mut updates: EventWriter<ClientAuthoritativeUpdate<C>>,
+) {
+    let Some(client_id) = local_client_id(local_client_id_res.as_deref(), &client) else {
+        return;
+    };
+    for (entity, owner, component) in &q_changed {
+        if owner.0 == client_id.get() {
+            updates.send(ClientAuthoritativeUpdate {
+                entity,
+                value: component.clone(),
+            });
+        }
+    }
+}
+
+/// Applies the values sent by the owners of the entities, updates for entities owned by someone
+/// else are ignored.
+pub fn server_client_authoritative_system<C: Component + Clone>(
+    mut updates: EventReader<FromClient<ClientAuthoritativeUpdate<C>>>,
+    mut q_owned: Query<(&NetworkOwner, &mut C)>,
+    validation: Option<Res<AuthorityValidation<C>>>,
+) {
+    for FromClient { client_id, event } in updates.read() {
+        let Ok((owner, mut component)) = q_owned.get_mut(event.entity) else {
+            continue;
+        };
+        if owner.0 != client_id.get() {
+            debug!(
+                "ignored {} of {:?} from client {client_id:?}, it is not the owner",
+                type_name::<C>(),
+                event.entity
+            );
+            continue;
+        }
+        let value = match &validation {
+            Some(validation) => (validation.0)(&component, &event.value),
+            None => Some(event.value.clone()),
+        };
+        match value {
+            Some(value) => *component = value,
+            None => debug!(
+                "rejected {} of {:?} from client {client_id:?}",
+                type_name::<C>(),
+                event.entity
+            ),
+        }
+    }
+}
+
+pub trait AppAuthorityExt {
+    /// Register a component that is written by the client in the [`NetworkOwner`] of an entity,
+    /// e.g. cosmetic state or a cursor position.
+    ///
+    /// The owner sends its changes to the server, which replicates them to all other clients
+    /// where `C` is interpolated like components registered with `replicate_interpolated`.
+    /// Do not register `C` with `replicate_interpolated` as well. Owned entities must not be
+    /// [`Interpolated`] on the owning client, e.g. spawn them with `OwnerPredicted`.
+    fn replicate_client_authoritative<C>(&mut self) -> &mut Self
+    where
+        C: Component + Interpolate + Clone + Serialize + DeserializeOwned;
+
+    /// Same as [`AppAuthorityExt::replicate_client_authoritative`], but every value is passed to
+    /// `validate` on the server before it is applied.
+    fn replicate_client_authoritative_with_validation<C>(
+        &mut self,
+        validate: fn(&C, &C) -> Option<C>,
+    ) -> &mut Self
+    where
+        C: Component + Interpolate + Clone + Serialize + DeserializeOwned;
+}
+
+impl AppAuthorityExt for App {
+    fn replicate_client_authoritative<C>(&mut self) -> &mut Self
+    where
+        C: Component + Interpolate + Clone + Serialize + DeserializeOwned,
+    {
+        self.replicate_interpolated::<C>()
+            .add_mapped_client_event::<ClientAuthoritativeUpdate<C>>(ChannelKind::Ordered)
+            .add_systems(
+                Update,
+                (
+                    send_client_authoritative_system::<C>.run_if(client_connected), // Runs only on clients.
+                    server_client_authoritative_system::<C>.run_if(server_or_singleplayer), // Runs only on the server or a single player.
+                ),
+            )
+    }
+
+    fn replicate_client_authoritative_with_validation<C>(
+        &mut self,
+        validate: fn(&C, &C) -> Option<C>,
+    ) -> &mut Self
+    where
+        C: Component + Interpolate + Clone + Serialize + DeserializeOwned,
+    {
+        self.insert_resource(AuthorityValidation::<C>(validate))
+            .replicate_client_authoritative::<C>()
+    }
+}
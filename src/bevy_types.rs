@@ -0,0 +1,272 @@
+#[cfg(feature = "color")]
+use bevy::color::Hsla;
+#[cfg(feature = "bevy_math")]
+use bevy::math::primitives::{Capsule2d, Capsule3d, Circle, Cuboid, Cylinder, Rectangle, Sphere};
+use bevy::math::{DVec2, DVec3, DVec4, FloatOrd, Quat, Rect, Vec2, Vec3, Vec3A, Vec4};
+
+use crate::interpolation::Interpolate;
+
+impl Interpolate for f32 {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        (self - other).abs()
+    }
+}
+
+impl Interpolate for f64 {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        self + (other - self) * t as f64
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        (self - other).abs() as f32
+    }
+}
+
+/// Discrete values switch to `other` halfway through the transition.
+impl Interpolate for usize {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        if t < 0.5 {
+            *self
+        } else {
+            other
+        }
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        self.abs_diff(*other) as f32
+    }
+}
+
+impl Interpolate for FloatOrd {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        FloatOrd(self.0.interpolate(other.0, t))
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        self.0.distance(&other.0)
+    }
+}
+
+impl Interpolate for Vec2 {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        self.lerp(other, t)
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        Vec2::distance(*self, *other)
+    }
+}
+
+impl Interpolate for Vec3 {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        self.lerp(other, t)
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        Vec3::distance(*self, *other)
+    }
+}
+
+impl Interpolate for Vec3A {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        self.lerp(other, t)
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        Vec3A::distance(*self, *other)
+    }
+}
+
+impl Interpolate for Vec4 {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        self.lerp(other, t)
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        Vec4::distance(*self, *other)
+    }
+}
+
+impl Interpolate for DVec2 {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        self.lerp(other, t as f64)
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        DVec2::distance(*self, *other) as f32
+    }
+}
+
+impl Interpolate for DVec3 {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        self.lerp(other, t as f64)
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        DVec3::distance(*self, *other) as f32
+    }
+}
+
+impl Interpolate for DVec4 {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        self.lerp(other, t as f64)
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        DVec4::distance(*self, *other) as f32
+    }
+}
+
+impl Interpolate for Quat {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        self.slerp(other, t)
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        self.angle_between(*other)
+    }
+}
+
+impl Interpolate for Rect {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        Rect {
+            min: self.min.lerp(other.min, t),
+            max: self.max.lerp(other.max, t),
+        }
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        self.min
+            .distance(other.min)
+            .max(self.max.distance(other.max))
+    }
+}
+
+/// Shortest signed difference from hue `from` to hue `to` in degrees.
+#[cfg(feature = "color")]
+fn hue_difference(from: f32, to: f32) -> f32 {
+    (to - from + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// Takes the shortest path around the hue wheel, saturation, lightness and alpha are lerped.
+#[cfg(feature = "color")]
+impl Interpolate for Hsla {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        Hsla::new(
+            (self.hue + hue_difference(self.hue, other.hue) * t).rem_euclid(360.0),
+            self.saturation.interpolate(other.saturation, t),
+            self.lightness.interpolate(other.lightness, t),
+            self.alpha.interpolate(other.alpha, t),
+        )
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        Vec4::new(
+            hue_difference(self.hue, other.hue) / 360.0,
+            self.saturation - other.saturation,
+            self.lightness - other.lightness,
+            self.alpha - other.alpha,
+        )
+        .length()
+    }
+}
+
+#[cfg(feature = "bevy_math")]
+impl Interpolate for Circle {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        Circle::new(self.radius.interpolate(other.radius, t))
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        self.radius.distance(&other.radius)
+    }
+}
+
+#[cfg(feature = "bevy_math")]
+impl Interpolate for Sphere {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        Sphere::new(self.radius.interpolate(other.radius, t))
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        self.radius.distance(&other.radius)
+    }
+}
+
+#[cfg(feature = "bevy_math")]
+impl Interpolate for Capsule2d {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        Capsule2d {
+            radius: self.radius.interpolate(other.radius, t),
+            half_length: self.half_length.interpolate(other.half_length, t),
+        }
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        self.radius
+            .distance(&other.radius)
+            .max(self.half_length.distance(&other.half_length))
+    }
+}
+
+#[cfg(feature = "bevy_math")]
+impl Interpolate for Capsule3d {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        Capsule3d {
+            radius: self.radius.interpolate(other.radius, t),
+            half_length: self.half_length.interpolate(other.half_length, t),
+        }
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        self.radius
+            .distance(&other.radius)
+            .max(self.half_length.distance(&other.half_length))
+    }
+}
+
+#[cfg(feature = "bevy_math")]
+impl Interpolate for Cylinder {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        Cylinder {
+            radius: self.radius.interpolate(other.radius, t),
+            half_height: self.half_height.interpolate(other.half_height, t),
+        }
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        self.radius
+            .distance(&other.radius)
+            .max(self.half_height.distance(&other.half_height))
+    }
+}
+
+#[cfg(feature = "bevy_math")]
+impl Interpolate for Rectangle {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        Rectangle {
+            half_size: self.half_size.lerp(other.half_size, t),
+        }
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        self.half_size.distance(other.half_size)
+    }
+}
+
+#[cfg(feature = "bevy_math")]
+impl Interpolate for Cuboid {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        Cuboid {
+            half_size: self.half_size.lerp(other.half_size, t),
+        }
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        self.half_size.distance(other.half_size)
+    }
+}
@@ -0,0 +1,93 @@
+use std::error::Error;
+
+use bevy_replicon::bincode;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Result of a [`SnapshotSerializationFormat`], the input of `deserialize` is never trusted.
+pub type FormatResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Format to persist serializable state, e.g. a
+/// [`SnapshotBuffer`](crate::interpolation::SnapshotBuffer) or a
+/// [`PredictedEventHistory`](crate::prediction::PredictedEventHistory) in replay files.
+///
+/// Replication itself always uses `bincode` as dictated by replicon.
+pub trait SnapshotSerializationFormat {
+    fn serialize<T: Serialize>(value: &T) -> FormatResult<Vec<u8>>;
+
+    fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> FormatResult<T>;
+}
+
+/// The format replicon uses for replication.
+pub struct Bincode;
+
+impl SnapshotSerializationFormat for Bincode {
+    fn serialize<T: Serialize>(value: &T) -> FormatResult<Vec<u8>> {
+        Ok(bincode::serialize(value)?)
+    }
+
+    fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> FormatResult<T> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+}
+
+/// Compact format with variable length integers.
+#[cfg(feature = "postcard")]
+pub struct Postcard;
+
+#[cfg(feature = "postcard")]
+impl SnapshotSerializationFormat for Postcard {
+    fn serialize<T: Serialize>(value: &T) -> FormatResult<Vec<u8>> {
+        Ok(postcard::to_allocvec(value)?)
+    }
+
+    fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> FormatResult<T> {
+        Ok(postcard::from_bytes(bytes)?)
+    }
+}
+
+/// Human-readable format, e.g. to inspect recordings.
+#[cfg(feature = "json")]
+pub struct Json;
+
+#[cfg(feature = "json")]
+impl SnapshotSerializationFormat for Json {
+    fn serialize<T: Serialize>(value: &T) -> FormatResult<Vec<u8>> {
+        Ok(serde_json::to_vec(value)?)
+    }
+
+    fn deserialize<T: DeserializeOwned>(bytes: &[u8]) -> FormatResult<T> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Value = (u32, String);
+
+    fn truncated<F: SnapshotSerializationFormat>() -> FormatResult<Value> {
+        let bytes = F::serialize(&(7u32, "snapshot".to_string())).unwrap();
+        F::deserialize(&bytes[..bytes.len() - 3])
+    }
+
+    #[test]
+    fn bincode_rejects_malformed_bytes() {
+        assert!(truncated::<Bincode>().is_err());
+        assert!(Bincode::deserialize::<Value>(&[0xff; 16]).is_err());
+    }
+
+    #[cfg(feature = "postcard")]
+    #[test]
+    fn postcard_rejects_malformed_bytes() {
+        assert!(truncated::<Postcard>().is_err());
+        assert!(Postcard::deserialize::<Value>(&[0xff; 16]).is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_rejects_malformed_bytes() {
+        assert!(truncated::<Json>().is_err());
+        assert!(Json::deserialize::<Value>(b"{not json").is_err());
+    }
+}
@@ -1,118 +1,737 @@
-use std::{collections::VecDeque, io::Cursor};
+use std::{
+    any::{type_name, TypeId},
+    collections::VecDeque,
+    io::Cursor,
+    marker::PhantomData,
+};
 
+#[cfg(feature = "lod")]
+use bevy::render::view::Visibility;
 use bevy::{
     app::{App, PreUpdate},
     ecs::{
-        component::Component,
+        component::{Component, ComponentId},
         entity::Entity,
-        query::{Added, Or, With, Without},
+        event::EventReader,
+        query::{Added, Changed, Has, Or, With, Without},
+        reflect::ReflectResource,
         schedule::IntoSystemConfigs,
         system::{Commands, Query, Res},
+        world::DeferredWorld,
     },
     prelude::Resource,
     reflect::Reflect,
     time::Time,
-    utils::default,
+    utils::{default, HashMap, HashSet},
 };
 use bevy_replicon::{
     bincode,
-    core::replication::{
-        command_markers::MarkerConfig,
-        deferred_entity::DeferredEntity,
-        replication_registry::{
-            ctx::{RemoveCtx, WriteCtx},
-            rule_fns::RuleFns,
+    core::{
+        replication::{
+            command_markers::MarkerConfig,
+            deferred_entity::DeferredEntity,
+            replication_registry::{
+                ctx::{RemoveCtx, WriteCtx},
+                rule_fns::RuleFns,
+            },
         },
+        replicon_tick::RepliconTick,
     },
     prelude::{client_connected, AppMarkerExt, AppRuleExt},
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::{
-    prediction::{owner_prediction_init_system, predicted_snapshot_system, Predicted},
+    prediction::{
+        owner_prediction_init_system, predicted_snapshot_system, register_session_reset,
+        remove_from_all, OwnershipChanged, Predicted, RemotePredicted,
+    },
     InterpolationSet,
 };
 
 pub trait Interpolate {
     fn interpolate(&self, other: Self, t: f32) -> Self;
+
+    /// Distance between two values, used to measure mispredictions, e.g. for
+    /// [`PredictionSmoothing`](crate::prediction::PredictionSmoothing) and
+    /// [`CorrectionTolerance`](crate::prediction::CorrectionTolerance).
+    fn distance(&self, other: &Self) -> f32;
+}
+
+macro_rules! impl_interpolate_tuple {
+    ($($name:ident $index:tt),+) => {
+        impl<$($name: Interpolate),+> Interpolate for ($($name,)+) {
+            fn interpolate(&self, other: Self, t: f32) -> Self {
+                ($(self.$index.interpolate(other.$index, t),)+)
+            }
+
+            fn distance(&self, other: &Self) -> f32 {
+                (0.0 $(+ self.$index.distance(&other.$index).powi(2))+).sqrt()
+            }
+        }
+    };
 }
 
+impl_interpolate_tuple!(A 0, B 1);
+impl_interpolate_tuple!(A 0, B 1, C 2);
+impl_interpolate_tuple!(A 0, B 1, C 2, D 3);
+impl_interpolate_tuple!(A 0, B 1, C 2, D 3, E 4);
+impl_interpolate_tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_interpolate_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_interpolate_tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+impl<T: Interpolate + Copy, const N: usize> Interpolate for [T; N] {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        std::array::from_fn(|i| self[i].interpolate(other[i], t))
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        self.iter()
+            .zip(other)
+            .map(|(a, b)| a.distance(b).powi(2))
+            .sum::<f32>()
+            .sqrt()
+    }
+}
+
+/// Interpolates matching elements by index. The length switches halfway through the transition,
+/// elements only one side has are taken from that side.
+impl<T: Interpolate + Clone> Interpolate for Vec<T> {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        let len = if t < 0.5 { self.len() } else { other.len() };
+        (0..len)
+            .map(|i| match (self.get(i), other.get(i)) {
+                (Some(a), Some(b)) => a.interpolate(b.clone(), t),
+                (Some(a), None) => a.clone(),
+                (None, Some(b)) => b.clone(),
+                (None, None) => unreachable!("index is within one of the lengths"),
+            })
+            .collect()
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        self.iter()
+            .zip(other)
+            .map(|(a, b)| a.distance(b).powi(2))
+            .sum::<f32>()
+            .sqrt()
+    }
+}
+
+/// Marks entities whose replicated components are interpolated.
+///
+/// Mutually exclusive with [`Predicted`], inserting it removes [`Predicted`]. If both are inserted
+/// during the same tick, e.g. in one bundle, [`Predicted`] is kept.
 #[derive(Component, Deserialize, Serialize, Reflect)]
+#[component(on_add = remove_predicted)]
 pub struct Interpolated;
 
+fn remove_predicted(mut world: DeferredWorld, entity: Entity, _component_id: ComponentId) {
+    let entity_ref = world.entity(entity);
+    // `Predicted` removes `Interpolated` when inserted together
+    let inserted_together = entity_ref
+        .get_change_ticks::<Predicted>()
+        .zip(entity_ref.get_change_ticks::<Interpolated>())
+        .is_some_and(|(predicted, interpolated)| predicted.added == interpolated.added);
+    if !inserted_together {
+        world.commands().entity(entity).remove::<Predicted>();
+    }
+}
+
+/// Frame index of an animated sprite, e.g. into a texture atlas.
+///
+/// Switches to the next frame halfway between two snapshots instead of blending.
+#[derive(
+    Component, Deserialize, Serialize, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq,
+)]
+pub struct SpriteFrameIndex(pub usize);
+
+impl Interpolate for SpriteFrameIndex {
+    fn interpolate(&self, other: Self, t: f32) -> Self {
+        Self(self.0.interpolate(other.0, t))
+    }
+
+    fn distance(&self, other: &Self) -> f32 {
+        self.0.distance(&other.0)
+    }
+}
+
+/// Interpolation quality of an interpolated entity, entities without it use [`InterpolationLod::High`].
+///
+/// With the `lod` feature it is updated from the camera distance by
+/// `interpolation_lod_update_system`.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InterpolationLod {
+    /// Interpolates every frame with [`SnapshotInterpolationConfig::curve`].
+    #[default]
+    High,
+    /// Interpolates every frame, always linear.
+    Medium,
+    /// Jumps to the latest snapshot when it arrives.
+    Low,
+    /// Leaves the component untouched.
+    Disabled,
+}
+
+impl InterpolationLod {
+    /// Maximum number of snapshots kept in the buffer.
+    pub fn snapshot_capacity(&self) -> usize {
+        match self {
+            InterpolationLod::High | InterpolationLod::Medium => 2,
+            InterpolationLod::Low | InterpolationLod::Disabled => 1,
+        }
+    }
+}
+
+/// Interpolated entity outside the view of every camera, it is not interpolated until it is
+/// visible again.
+///
+/// With the `lod` feature it is maintained by `interpolation_frustum_cull_system` while the
+/// `InterpolationFrustumCull` resource exists.
+/// It can also be inserted and removed manually, e.g. to skip hidden entities without the `lod`
+/// feature.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+pub struct InterpolationCulled;
+
+/// Overrides [`SnapshotInterpolationConfig`] for a single entity, e.g. the local player or bosses.
+///
+/// Fields left at `None` fall back to the global config.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default, PartialEq)]
+pub struct InterpolationOverride {
+    /// Tick rate the interpolation between two snapshots is timed with.
+    pub max_tick_rate: Option<u16>,
+    /// Maximum number of snapshots kept in the buffer.
+    pub max_snapshots: Option<usize>,
+    /// Keep moving past the latest snapshot by up to one tick while the next one is late,
+    /// falls back to [`SnapshotInterpolationConfig::extrapolation`].
+    pub extrapolate: Option<bool>,
+}
+
+impl InterpolationOverride {
+    /// Fills the fields left at `None` from `fallback`.
+    pub fn or(self, fallback: InterpolationOverride) -> Self {
+        Self {
+            max_tick_rate: self.max_tick_rate.or(fallback.max_tick_rate),
+            max_snapshots: self.max_snapshots.or(fallback.max_snapshots),
+            extrapolate: self.extrapolate.or(fallback.extrapolate),
+        }
+    }
+}
+
+/// Assigns an entity to a group of [`SnapshotGroupRegistry`], e.g. all projectiles or all
+/// vehicles, that shares the same interpolation settings.
+#[derive(Component, Deserialize, Serialize, Reflect, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SnapshotGroup(pub u8);
+
+/// Interpolation settings of each [`SnapshotGroup`].
+///
+/// An [`InterpolationOverride`] on the entity itself takes precedence over its group.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct SnapshotGroupRegistry(pub HashMap<u8, InterpolationOverride>);
+
+/// Override of the entity merged with the settings of its [`SnapshotGroup`].
+fn effective_override(
+    config_override: Option<&InterpolationOverride>,
+    group: Option<&SnapshotGroup>,
+    groups: Option<&SnapshotGroupRegistry>,
+) -> Option<InterpolationOverride> {
+    let group_override = group
+        .zip(groups)
+        .and_then(|(group, groups)| groups.0.get(&group.0))
+        .copied();
+    match (config_override.copied(), group_override) {
+        (Some(config_override), Some(group_override)) => Some(config_override.or(group_override)),
+        (config_override, group_override) => config_override.or(group_override),
+    }
+}
+
 #[derive(Deserialize, Serialize, Reflect)]
 pub struct Snapshot<T: Component + Interpolate + Clone> {
-    tick: u32,
-    value: T,
+    pub tick: u32,
+    pub value: T,
 }
 
 #[derive(Component, Deserialize, Serialize, Reflect)]
 pub struct SnapshotBuffer<T: Component + Interpolate + Clone> {
-    pub buffer: VecDeque<T>,
-    pub time_since_last_snapshot: f32,
+    pub buffer: VecDeque<Snapshot<T>>,
+    /// Only reset by [`SnapshotBuffer::insert`] and [`SnapshotBuffer::reset`], read with
+    /// [`SnapshotBuffer::age_secs`].
+    time_since_last_snapshot: f32,
     pub latest_snapshot_tick: u32,
+    /// Whether the latest snapshot arrived during the current frame, its age is not advanced
+    /// before the next frame.
+    #[serde(skip)]
+    #[reflect(ignore)]
+    just_received: bool,
 }
 
-#[derive(Resource, Serialize, Deserialize, Debug)]
+/// Received values of `T` waiting for the [`SnapshotHook`] before they enter the buffer.
+#[derive(Component)]
+pub struct PendingSnapshots<T: Component + Interpolate + Clone>(pub Vec<Snapshot<T>>);
+
+/// Hook registered with [`AppInterpolationExt::replicate_interpolated_with_hook`].
+#[derive(Resource)]
+pub struct SnapshotHook<T: Component + Interpolate + Clone>(
+    Box<dyn Fn(&T, &SnapshotBuffer<T>) -> Option<T> + Send + Sync>,
+);
+
+/// Reflected as a resource, so the settings can be tuned at runtime, e.g. with an inspector.
+#[derive(Resource, Serialize, Deserialize, Reflect, Debug, Clone)]
+#[reflect(Resource)]
 pub struct SnapshotInterpolationConfig {
     pub max_tick_rate: u16,
+    /// Blend curve applied to the interpolation factor between two snapshots.
+    pub curve: InterpolationCurve,
+    /// Snapshots older than this are evicted, except for the latest one.
+    pub max_snapshot_age_secs: f32,
+    /// Maximum number of snapshots kept in each buffer, e.g. raised for lookups with
+    /// [`SnapshotBuffer::get_at_tick`]. Interpolation only needs the latest two.
+    pub max_snapshots: usize,
+    /// What happens while the next snapshot is late, see [`InterpolationOverride::extrapolate`]
+    /// for single entities.
+    pub extrapolation: ExtrapolationMode,
+}
+
+/// Interpolation settings of snapshot channels, keyed by the id passed to
+/// [`AppInterpolationExt::replicate_interpolated_on_channel`].
+///
+/// Channels without settings use the global [`SnapshotInterpolationConfig`].
+#[derive(Resource, Clone, Debug, Default)]
+pub struct SnapshotChannels(pub HashMap<u8, SnapshotInterpolationConfig>);
+
+/// Snapshot channel of `T`, registered with [`AppInterpolationExt::replicate_interpolated_on_channel`].
+#[derive(Resource)]
+pub struct SnapshotChannel<T> {
+    pub id: u8,
+    marker: PhantomData<T>,
+}
+
+/// Settings of the snapshot channel of `T`, falling back to the global config.
+fn channel_config<'a, T>(
+    config: &'a SnapshotInterpolationConfig,
+    channel: Option<&SnapshotChannel<T>>,
+    channels: Option<&'a SnapshotChannels>,
+) -> &'a SnapshotInterpolationConfig {
+    channel
+        .zip(channels)
+        .and_then(|(channel, channels)| channels.0.get(&channel.id))
+        .unwrap_or(config)
+}
+
+/// Skips interpolation and applies every snapshot as soon as it arrives.
+///
+/// Meant for clients that share the process with the server, where interpolation only adds
+/// latency.
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct LocalMode;
+
+/// Behavior of interpolated entities once the latest snapshot is reached.
+#[derive(Serialize, Deserialize, Reflect, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ExtrapolationMode {
+    /// Stays at the latest snapshot until the next one arrives.
+    #[default]
+    Disabled,
+    /// Keeps moving with the change between the latest two snapshots, by up to one tick.
+    Velocity,
+}
+
+/// Curve that maps the linear progress between two snapshots to the interpolation factor.
+#[derive(Serialize, Deserialize, Reflect, Debug, Default, Clone, Copy, PartialEq)]
+pub enum InterpolationCurve {
+    #[default]
+    Linear,
+    /// Starts slow, the value is the exponent of the curve.
+    EaseIn(f32),
+    /// Ends slow, the value is the exponent of the curve.
+    EaseOut(f32),
+    /// Starts and ends slow, the value is the exponent of the curve.
+    EaseInOut(f32),
+    /// Holds the older snapshot until the next one is due.
+    Step,
+}
+
+impl InterpolationCurve {
+    /// Maps `t` in `0..=1` onto the curve.
+    pub fn apply(&self, t: f32) -> f32 {
+        match *self {
+            InterpolationCurve::Linear => t,
+            InterpolationCurve::EaseIn(power) => t.powf(power),
+            InterpolationCurve::EaseOut(power) => 1.0 - (1.0 - t).powf(power),
+            InterpolationCurve::EaseInOut(power) => {
+                if t < 0.5 {
+                    0.5 * (2.0 * t).powf(power)
+                } else {
+                    1.0 - 0.5 * (2.0 - 2.0 * t).powf(power)
+                }
+            }
+            InterpolationCurve::Step => {
+                if t < 1.0 {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+        }
+    }
 }
 
 #[derive(Component)]
 pub struct RecordSnapshotsMarker;
 
+/// Bookkeeping of interpolation registrations.
+#[derive(Resource, Default)]
+struct RegisteredInterpolationTypes {
+    marker_registered: bool,
+    types: HashSet<TypeId>,
+}
+
+/// Records the registration of `T`.
+///
+/// # Panics
+///
+/// Panics if `T` was already registered, its systems and marker functions would be added twice.
+fn register_interpolated_type<T: Component>(app: &mut App) {
+    let newly_registered = app
+        .world_mut()
+        .get_resource_or_insert_with(RegisteredInterpolationTypes::default)
+        .types
+        .insert(TypeId::of::<T>());
+    assert!(
+        newly_registered,
+        "{} is already registered for interpolation, register each component only once",
+        type_name::<T>()
+    );
+}
+
+/// Registers [`RecordSnapshotsMarker`] with replicon, only the first call has an effect.
+///
+/// Called by every registration that sets marker functions, so the order in which components
+/// and the plugin are registered does not matter.
+pub(crate) fn register_record_snapshots_marker(app: &mut App) {
+    let mut registered = app
+        .world_mut()
+        .get_resource_or_insert_with(RegisteredInterpolationTypes::default);
+    if registered.marker_registered {
+        return;
+    }
+    registered.marker_registered = true;
+    app.register_marker_with::<RecordSnapshotsMarker>(MarkerConfig {
+        need_history: true,
+        ..default()
+    });
+}
+
+impl<T: Component + Interpolate + Clone> Default for SnapshotBuffer<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T: Component + Interpolate + Clone> SnapshotBuffer<T> {
     pub fn new() -> Self {
         Self {
             buffer: VecDeque::new(),
             time_since_last_snapshot: 0.0,
             latest_snapshot_tick: 0,
+            just_received: false,
         }
     }
+
+    /// Appends a snapshot, the buffer is trimmed by `snapshot_buffer_eviction_system` according
+    /// to [`SnapshotInterpolationConfig::max_snapshots`] and its maximum snapshot age.
     pub fn insert(&mut self, element: T, tick: u32) {
-        if self.buffer.len() > 1 {
-            self.buffer.pop_front();
-        }
-        self.buffer.push_back(element);
+        self.buffer.push_back(Snapshot {
+            tick,
+            value: element,
+        });
         self.time_since_last_snapshot = 0.0;
         self.latest_snapshot_tick = tick;
+        self.just_received = true;
+    }
+
+    /// Drops all snapshots, e.g. after a teleport or a level transition where interpolating from
+    /// the stored snapshots would be a visible glitch.
+    pub fn reset(&mut self) {
+        self.buffer.clear();
+        self.time_since_last_snapshot = 0.0;
+        self.latest_snapshot_tick = 0;
+        self.just_received = false;
     }
 
+    /// Value of the latest snapshot.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the buffer is empty, e.g. before the first update of the component arrived.
     pub fn latest_snapshot(&self) -> T {
-        self.buffer.iter().last().unwrap().clone()
+        self.buffer.iter().last().unwrap().value.clone()
+    }
+
+    /// Replaces the value of the latest snapshot, keeping its tick.
+    pub fn correct_latest(&mut self, value: T) {
+        if let Some(latest) = self.buffer.back_mut() {
+            latest.value = value;
+        }
+    }
+
+    /// Returns the latest snapshot at or before `tick`, comparing ticks wrapping-aware.
+    ///
+    /// Only snapshots still in the buffer are found, see
+    /// [`SnapshotInterpolationConfig::max_snapshots`].
+    pub fn get_at_tick(&self, tick: u32) -> Option<&T> {
+        let tick = RepliconTick::new(tick);
+        self.buffer
+            .iter()
+            .rev()
+            .find(|snapshot| RepliconTick::new(snapshot.tick) <= tick)
+            .map(|snapshot| &snapshot.value)
+    }
+
+    /// Interpolates between the snapshots found for `tick_a` and `tick_b` by [`Self::get_at_tick`].
+    pub fn interpolate_at_tick(&self, tick_a: u32, tick_b: u32, t: f32) -> Option<T> {
+        let a = self.get_at_tick(tick_a)?;
+        let b = self.get_at_tick(tick_b)?;
+        Some(a.interpolate(b.clone(), t))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// The two latest snapshots, which are interpolated between.
+    fn latest_pair(&self) -> Option<(&Snapshot<T>, &Snapshot<T>)> {
+        let len = self.buffer.len();
+        if len < 2 {
+            return None;
+        }
+        Some((&self.buffer[len - 2], &self.buffer[len - 1]))
+    }
+
+    /// Whether there are at least two snapshots to interpolate between.
+    pub fn has_enough_for_interpolation(&self) -> bool {
+        self.len() >= 2
     }
 
     pub fn latest_snapshot_tick(&self) -> u32 {
         self.latest_snapshot_tick
     }
 
+    #[deprecated(note = "use `age_secs` instead")]
     pub fn age(&self) -> f32 {
+        self.age_secs()
+    }
+
+    /// Seconds since the latest snapshot was inserted.
+    pub fn age_secs(&self) -> f32 {
         self.time_since_last_snapshot
     }
+
+    /// Whether the latest snapshot is older than `threshold` seconds.
+    pub fn is_stale(&self, threshold: f32) -> bool {
+        self.time_since_last_snapshot > threshold
+    }
+
+    /// Advances the age of the latest snapshot, except on the frame it arrived.
+    pub(crate) fn advance(&mut self, delta_secs: f32) {
+        if self.just_received {
+            self.just_received = false;
+            return;
+        }
+        self.time_since_last_snapshot += delta_secs;
+    }
+
+    /// Restarts the age of the latest snapshot, e.g. after its meaning changed.
+    pub(crate) fn reset_age(&mut self) {
+        self.time_since_last_snapshot = 0.0;
+    }
+
+    /// Age in seconds of the oldest stored snapshot.
+    pub fn oldest_snapshot_age(&self, tick_duration: f32) -> f32 {
+        self.buffer
+            .front()
+            .map_or(0.0, |oldest| self.snapshot_age(oldest.tick, tick_duration))
+    }
+
+    /// Removes snapshots older than `max_age` seconds, the latest snapshot is always kept.
+    pub fn evict_older_than(&mut self, max_age: f32, tick_duration: f32) {
+        while self.buffer.len() > 1 {
+            let oldest_tick = self.buffer[0].tick;
+            if self.snapshot_age(oldest_tick, tick_duration) <= max_age {
+                break;
+            }
+            self.buffer.pop_front();
+        }
+    }
+
+    fn snapshot_age(&self, tick: u32, tick_duration: f32) -> f32 {
+        self.latest_snapshot_tick.wrapping_sub(tick) as f32 * tick_duration
+            + self.time_since_last_snapshot
+    }
 }
 
 /// Interpolate between snapshots.
+///
+/// Entities marked with [`InterpolationCulled`] are skipped. With the `lod` feature entities with
+/// `Visibility::Hidden` are skipped as well, without it `Visibility` (part of `bevy_render`)
+/// isn't available and hidden entities are interpolated like visible ones, insert
+/// [`InterpolationCulled`] to skip them instead.
 pub fn snapshot_interpolation_system<T: Component + Interpolate + Clone>(
-    mut q: Query<(&mut T, &mut SnapshotBuffer<T>), (With<Interpolated>, Without<Predicted>)>,
+    mut q: Query<
+        (
+            &mut T,
+            &mut SnapshotBuffer<T>,
+            Option<&InterpolationLod>,
+            Option<&InterpolationOverride>,
+            Option<&SnapshotGroup>,
+            VisibilityData,
+            Has<InterpolationCulled>,
+        ),
+        (
+            With<Interpolated>,
+            Without<Predicted>,
+            Without<RemotePredicted<T>>,
+        ),
+    >,
     time: Res<Time>,
     config: Res<SnapshotInterpolationConfig>,
+    channel: Option<Res<SnapshotChannel<T>>>,
+    channels: Option<Res<SnapshotChannels>>,
+    groups: Option<Res<SnapshotGroupRegistry>>,
+    local_mode: Option<Res<LocalMode>>,
 ) {
-    for (mut component, mut snapshot_buffer) in q.iter_mut() {
-        let buffer = &snapshot_buffer.buffer;
-        let elapsed = snapshot_buffer.time_since_last_snapshot;
-        if buffer.len() < 2 {
+    let config = channel_config(&config, channel.as_deref(), channels.as_deref());
+    for (mut component, mut snapshot_buffer, lod, config_override, group, visibility, culled) in
+        q.iter_mut()
+    {
+        // Hidden entities keep their timing, so they resume smoothly once visible again
+        if culled || is_hidden(&visibility) {
+            snapshot_buffer.advance(time.delta_secs());
             continue;
         }
+        let lod = if local_mode.is_some() {
+            InterpolationLod::Low
+        } else {
+            lod.copied().unwrap_or_default()
+        };
+        let buffer = &snapshot_buffer.buffer;
+        let elapsed = snapshot_buffer.age_secs();
+        match lod {
+            InterpolationLod::Disabled => continue,
+            InterpolationLod::Low => {
+                // A fresh snapshot was just inserted
+                if elapsed == 0.0 {
+                    if let Some(latest) = buffer.back() {
+                        *component = latest.value.clone();
+                    }
+                }
+                snapshot_buffer.advance(time.delta_secs());
+                continue;
+            }
+            InterpolationLod::High | InterpolationLod::Medium => {}
+        }
+        let Some((from, to)) = snapshot_buffer.latest_pair() else {
+            continue;
+        };
 
-        let tick_duration = 1.0 / (config.max_tick_rate as f32);
+        let config_override =
+            effective_override(config_override, group, groups.as_deref()).unwrap_or_default();
+        let max_tick_rate = config_override
+            .max_tick_rate
+            .unwrap_or(config.max_tick_rate);
+        let tick_duration = 1.0 / (max_tick_rate as f32);
 
-        if elapsed > tick_duration + time.delta_secs() {
-            continue;
+        let extrapolate = config_override
+            .extrapolate
+            .unwrap_or(config.extrapolation == ExtrapolationMode::Velocity);
+        let t = if extrapolate && elapsed > tick_duration {
+            (elapsed / tick_duration).min(2.0)
+        } else {
+            if elapsed > tick_duration + time.delta_secs() {
+                continue;
+            }
+
+            let curve = match lod {
+                InterpolationLod::Medium => InterpolationCurve::Linear,
+                _ => config.curve,
+            };
+            curve.apply((elapsed / tick_duration).clamp(0., 1.))
+        };
+        *component = from.value.interpolate(to.value.clone(), t);
+        snapshot_buffer.advance(time.delta_secs());
+    }
+}
+
+/// Visibility of interpolated entities, hidden entities are not interpolated.
+///
+/// Requires `bevy_render`, so it is only queried with the `lod` feature.
+#[cfg(feature = "lod")]
+type VisibilityData = Option<&'static Visibility>;
+#[cfg(not(feature = "lod"))]
+type VisibilityData = ();
+
+#[cfg(feature = "lod")]
+fn is_hidden(visibility: &Option<&Visibility>) -> bool {
+    matches!(visibility, Some(Visibility::Hidden))
+}
+
+#[cfg(not(feature = "lod"))]
+fn is_hidden(_visibility: &()) -> bool {
+    false
+}
+
+/// Evicts snapshots older than [`SnapshotInterpolationConfig::max_snapshot_age_secs`]
+/// or exceeding [`SnapshotInterpolationConfig::max_snapshots`], which is overridden by
+/// [`InterpolationOverride::max_snapshots`] and limited by the
+/// [`InterpolationLod::snapshot_capacity`].
+pub fn snapshot_buffer_eviction_system<T: Component + Interpolate + Clone>(
+    mut q: Query<(
+        &mut SnapshotBuffer<T>,
+        Option<&InterpolationLod>,
+        Option<&InterpolationOverride>,
+        Option<&SnapshotGroup>,
+    )>,
+    config: Res<SnapshotInterpolationConfig>,
+    channel: Option<Res<SnapshotChannel<T>>>,
+    channels: Option<Res<SnapshotChannels>>,
+    groups: Option<Res<SnapshotGroupRegistry>>,
+) {
+    let config = channel_config(&config, channel.as_deref(), channels.as_deref());
+    for (mut snapshot_buffer, lod, config_override, group) in q.iter_mut() {
+        let config_override = effective_override(config_override, group, groups.as_deref());
+        let max_snapshots = config_override
+            .and_then(|config_override| config_override.max_snapshots)
+            .unwrap_or(config.max_snapshots);
+        let capacity = lod
+            .map(InterpolationLod::snapshot_capacity)
+            .map_or(max_snapshots, |capacity| capacity.min(max_snapshots));
+        // The latest snapshot is always kept
+        let excess = snapshot_buffer.buffer.len().saturating_sub(capacity.max(1));
+        snapshot_buffer.buffer.drain(..excess);
+        let max_tick_rate = config_override
+            .and_then(|config_override| config_override.max_tick_rate)
+            .unwrap_or(config.max_tick_rate);
+        let tick_duration = 1.0 / (max_tick_rate as f32);
+        if snapshot_buffer.len() > 1
+            && snapshot_buffer.oldest_snapshot_age(tick_duration) > config.max_snapshot_age_secs
+        {
+            snapshot_buffer.evict_older_than(config.max_snapshot_age_secs, tick_duration);
         }
+    }
+}
 
-        let t = (elapsed / tick_duration).clamp(0., 1.);
-        *component = buffer[0].interpolate(buffer[1].clone(), t);
-        snapshot_buffer.time_since_last_snapshot += time.delta_secs();
+/// Restarts the snapshot buffer age of entities whose owner changed.
+pub fn snapshot_buffer_ownership_system<T: Component + Interpolate + Clone>(
+    mut ownership_changes: EventReader<OwnershipChanged>,
+    mut q_buffers: Query<&mut SnapshotBuffer<T>>,
+) {
+    for change in ownership_changes.read() {
+        if let Ok(mut snapshot_buffer) = q_buffers.get_mut(change.entity) {
+            snapshot_buffer.reset_age();
+        }
     }
 }
 
@@ -144,6 +763,99 @@ pub fn write_snap_component<C: Clone + Interpolate + Component + DeserializeOwne
     Ok(())
 }
 
+/// Records snapshots for predicted entities, all other entities receive the value directly.
+fn write_predicted_only_component<C: Clone + Interpolate + Component + DeserializeOwned>(
+    ctx: &mut WriteCtx,
+    rule_fns: &RuleFns<C>,
+    entity: &mut DeferredEntity,
+    cursor: &mut Cursor<&[u8]>,
+) -> bincode::Result<()> {
+    if entity.contains::<Predicted>() {
+        return write_snap_component(ctx, rule_fns, entity, cursor);
+    }
+
+    let component: C = rule_fns.deserialize(ctx, cursor)?;
+    if let Some(mut current) = entity.get_mut::<C>() {
+        *current = component;
+    } else {
+        ctx.commands.entity(entity.id()).insert(component);
+    }
+
+    Ok(())
+}
+
+/// Stores received values in [`PendingSnapshots`] until [`snapshot_hook_system`] processed them.
+fn write_pending_snap_component<C: Clone + Interpolate + Component + DeserializeOwned>(
+    ctx: &mut WriteCtx,
+    rule_fns: &RuleFns<C>,
+    entity: &mut DeferredEntity,
+    cursor: &mut Cursor<&[u8]>,
+) -> bincode::Result<()> {
+    let snapshot = Snapshot {
+        tick: ctx.message_tick.get(),
+        value: rule_fns.deserialize(ctx, cursor)?,
+    };
+    if let Some(mut pending) = entity.get_mut::<PendingSnapshots<C>>() {
+        pending.0.push(snapshot);
+    } else {
+        ctx.commands
+            .entity(entity.id())
+            .insert(PendingSnapshots(vec![snapshot]));
+    }
+
+    Ok(())
+}
+
+fn remove_hooked_snap_component<C: Clone + Interpolate + Component + DeserializeOwned>(
+    ctx: &mut RemoveCtx,
+    entity: &mut DeferredEntity,
+) {
+    ctx.commands
+        .entity(entity.id())
+        .remove::<(SnapshotBuffer<C>, PendingSnapshots<C>)>()
+        .remove::<C>();
+}
+
+/// Passes received values through the [`SnapshotHook`] into the [`SnapshotBuffer`].
+pub fn snapshot_hook_system<T: Component + Interpolate + Clone>(
+    mut q_pending: Query<
+        (
+            Entity,
+            &mut PendingSnapshots<T>,
+            Option<&mut SnapshotBuffer<T>>,
+        ),
+        Changed<PendingSnapshots<T>>,
+    >,
+    hook: Res<SnapshotHook<T>>,
+    mut commands: Commands,
+) {
+    for (entity, mut pending, snapshot_buffer) in &mut q_pending {
+        if pending.0.is_empty() {
+            continue;
+        }
+        match snapshot_buffer {
+            Some(mut snapshot_buffer) => {
+                for snapshot in pending.0.drain(..) {
+                    if let Some(value) = (hook.0)(&snapshot.value, &snapshot_buffer) {
+                        snapshot_buffer.insert(value, snapshot.tick);
+                    }
+                }
+            }
+            None => {
+                let mut snapshot_buffer = SnapshotBuffer::new();
+                for snapshot in pending.0.drain(..) {
+                    if let Some(value) = (hook.0)(&snapshot.value, &snapshot_buffer) {
+                        snapshot_buffer.insert(value, snapshot.tick);
+                    }
+                }
+                if !snapshot_buffer.is_empty() {
+                    commands.entity(entity).insert(snapshot_buffer);
+                }
+            }
+        }
+    }
+}
+
 fn remove_snap_component<C: Clone + Interpolate + Component + DeserializeOwned>(
     ctx: &mut RemoveCtx,
     entity: &mut DeferredEntity,
@@ -157,9 +869,32 @@ fn remove_snap_component<C: Clone + Interpolate + Component + DeserializeOwned>(
 pub trait AppInterpolationExt {
     /// Register a component to be replicated and interpolated between server updates
     /// Requires the component to implement the Interpolate trait
+    ///
+    /// Panics if `C` was already registered with any of the interpolation registrations.
     fn replicate_interpolated<C>(&mut self) -> &mut Self
     where
         C: Component + Interpolate + Clone + Serialize + DeserializeOwned;
+
+    /// Same as [`AppInterpolationExt::replicate_interpolated`], but `C` is interpolated and
+    /// evicted with the settings of the snapshot channel `channel_id` in [`SnapshotChannels`],
+    /// e.g. configured with `SnapshotPlugin::with_channel`.
+    fn replicate_interpolated_on_channel<C>(&mut self, channel_id: u8) -> &mut Self
+    where
+        C: Component + Interpolate + Clone + Serialize + DeserializeOwned;
+
+    /// Same as [`AppInterpolationExt::replicate_interpolated`], but every received value is passed
+    /// to `hook` together with the current buffer first. The hook can return a modified value or
+    /// `None` to discard the snapshot, e.g. for validation or coordinate transforms.
+    fn replicate_interpolated_with_hook<C, F>(&mut self, hook: F) -> &mut Self
+    where
+        C: Component + Interpolate + Clone + Serialize + DeserializeOwned,
+        F: Fn(&C, &SnapshotBuffer<C>) -> Option<C> + Send + Sync + 'static;
+
+    /// Register a component to be replicated and predicted for its owner.
+    /// All other clients receive the latest server value without interpolation.
+    fn replicate_predicted_only<C>(&mut self) -> &mut Self
+    where
+        C: Component + Interpolate + Clone + Serialize + DeserializeOwned;
 }
 
 impl AppInterpolationExt for App {
@@ -167,6 +902,47 @@ impl AppInterpolationExt for App {
     where
         T: Component + Interpolate + Clone + Serialize + DeserializeOwned,
     {
+        add_interpolation_systems::<T>(self).set_marker_fns::<RecordSnapshotsMarker, T>(
+            write_snap_component,
+            remove_snap_component::<T>,
+        )
+    }
+
+    fn replicate_interpolated_on_channel<T>(&mut self, channel_id: u8) -> &mut Self
+    where
+        T: Component + Interpolate + Clone + Serialize + DeserializeOwned,
+    {
+        self.insert_resource(SnapshotChannel::<T> {
+            id: channel_id,
+            marker: PhantomData,
+        })
+        .replicate_interpolated::<T>()
+    }
+
+    fn replicate_interpolated_with_hook<T, F>(&mut self, hook: F) -> &mut Self
+    where
+        T: Component + Interpolate + Clone + Serialize + DeserializeOwned,
+        F: Fn(&T, &SnapshotBuffer<T>) -> Option<T> + Send + Sync + 'static,
+    {
+        self.insert_resource(SnapshotHook::<T>(Box::new(hook)))
+            .add_systems(
+                PreUpdate,
+                snapshot_hook_system::<T>
+                    .in_set(InterpolationSet::Init)
+                    .run_if(client_connected),
+            );
+        add_interpolation_systems::<T>(self).set_marker_fns::<RecordSnapshotsMarker, T>(
+            write_pending_snap_component,
+            remove_hooked_snap_component::<T>,
+        )
+    }
+
+    fn replicate_predicted_only<T>(&mut self) -> &mut Self
+    where
+        T: Component + Interpolate + Clone + Serialize + DeserializeOwned,
+    {
+        register_interpolated_type::<T>(self);
+        register_session_reset(self, remove_from_all::<SnapshotBuffer<T>>);
         self.add_systems(
             PreUpdate,
             (snapshot_buffer_init_system::<T>.after(owner_prediction_init_system))
@@ -176,21 +952,107 @@ impl AppInterpolationExt for App {
         self.add_systems(
             PreUpdate,
             (
-                snapshot_interpolation_system::<T>,
+                snapshot_buffer_eviction_system::<T>,
                 predicted_snapshot_system::<T>,
             )
                 .chain()
                 .in_set(InterpolationSet::Interpolate)
                 .run_if(client_connected),
         )
-        .replicate::<T>()
-        .register_marker_with::<RecordSnapshotsMarker>(MarkerConfig {
-            need_history: true,
-            ..default()
-        })
-        .set_marker_fns::<RecordSnapshotsMarker, T>(
-            write_snap_component,
+        .replicate::<T>();
+        register_record_snapshots_marker(self);
+        self.set_marker_fns::<RecordSnapshotsMarker, T>(
+            write_predicted_only_component,
             remove_snap_component::<T>,
         )
     }
 }
+
+/// Adds the systems and replication shared by all interpolated registrations of `T`.
+fn add_interpolation_systems<T>(app: &mut App) -> &mut App
+where
+    T: Component + Interpolate + Clone + Serialize + DeserializeOwned,
+{
+    register_interpolated_type::<T>(app);
+    register_session_reset(app, remove_from_all::<SnapshotBuffer<T>>);
+    app.add_systems(
+        PreUpdate,
+        (
+            snapshot_buffer_init_system::<T>.after(owner_prediction_init_system),
+            snapshot_buffer_ownership_system::<T>,
+        )
+            .in_set(InterpolationSet::Init)
+            .run_if(client_connected),
+    );
+    app.add_systems(
+        PreUpdate,
+        (
+            snapshot_buffer_eviction_system::<T>,
+            snapshot_interpolation_system::<T>,
+            predicted_snapshot_system::<T>,
+        )
+            .chain()
+            .in_set(InterpolationSet::Interpolate)
+            .run_if(client_connected),
+    )
+    .replicate::<T>();
+    register_record_snapshots_marker(app);
+    app
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::world::World;
+
+    use super::*;
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    struct Value(f32);
+
+    impl Interpolate for Value {
+        fn interpolate(&self, other: Self, t: f32) -> Self {
+            Self(self.0.interpolate(other.0, t))
+        }
+
+        fn distance(&self, other: &Self) -> f32 {
+            self.0.distance(&other.0)
+        }
+    }
+
+    #[test]
+    fn get_at_tick_keeps_older_snapshots() {
+        let mut buffer = SnapshotBuffer::new();
+        for tick in 1..=4 {
+            buffer.insert(Value(tick as f32), tick);
+        }
+        assert_eq!(buffer.get_at_tick(2), Some(&Value(2.0)));
+        assert_eq!(buffer.get_at_tick(10), Some(&Value(4.0)));
+        assert_eq!(buffer.get_at_tick(0), None);
+    }
+
+    #[test]
+    fn get_at_tick_across_wrap() {
+        let mut buffer = SnapshotBuffer::new();
+        buffer.insert(Value(0.0), u32::MAX - 1);
+        buffer.insert(Value(1.0), u32::MAX);
+        buffer.insert(Value(2.0), 1);
+        assert_eq!(buffer.get_at_tick(0), Some(&Value(1.0)));
+        assert_eq!(buffer.get_at_tick(1), Some(&Value(2.0)));
+        assert_eq!(buffer.get_at_tick(u32::MAX - 1), Some(&Value(0.0)));
+    }
+
+    #[test]
+    fn predicted_wins_when_inserted_together() {
+        let mut world = World::new();
+        let entity = world.spawn((Predicted, Interpolated)).id();
+        world.flush();
+        assert!(world.entity(entity).contains::<Predicted>());
+        assert!(!world.entity(entity).contains::<Interpolated>());
+
+        world.increment_change_tick();
+        world.entity_mut(entity).insert(Interpolated);
+        world.flush();
+        assert!(!world.entity(entity).contains::<Predicted>());
+        assert!(world.entity(entity).contains::<Interpolated>());
+    }
+}
@@ -0,0 +1,118 @@
+use std::{collections::VecDeque, marker::PhantomData};
+
+use bevy::{
+    app::{App, Plugin, PostUpdate},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::Changed,
+        schedule::{common_conditions::resource_changed, Condition, IntoSystemConfigs},
+        system::{Query, Res, ResMut, Resource},
+    },
+    utils::HashMap,
+};
+use bevy_replicon::{
+    core::replicon_tick::RepliconTick, prelude::server_or_singleplayer,
+    server::server_tick::ServerTick,
+};
+
+/// Default number of ticks kept in a [`ServerSnapshotHistory`].
+pub const SERVER_SNAPSHOT_HISTORY_DEPTH: usize = 64;
+
+/// Records the server state of `C` every tick into a [`ServerSnapshotHistory`].
+///
+/// Captures run in `PostUpdate`, after the simulation of the tick.
+pub struct ServerSnapshotCapture<C> {
+    /// Number of ticks kept in the history.
+    pub depth: usize,
+    marker: PhantomData<C>,
+}
+
+impl<C> ServerSnapshotCapture<C> {
+    pub fn new(depth: usize) -> Self {
+        Self {
+            depth,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<C> Default for ServerSnapshotCapture<C> {
+    fn default() -> Self {
+        Self::new(SERVER_SNAPSHOT_HISTORY_DEPTH)
+    }
+}
+
+impl<C: Component + Clone> Plugin for ServerSnapshotCapture<C> {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ServerSnapshotHistory::<C>::new(self.depth))
+            .add_systems(
+                PostUpdate,
+                server_snapshot_capture_system::<C>
+                    // Runs only on the server or a single player, once per tick.
+                    .run_if(server_or_singleplayer.and(resource_changed::<ServerTick>)),
+            );
+    }
+}
+
+/// Values of `C` changed during a single server tick.
+pub struct ServerSnapshot<C> {
+    pub tick: RepliconTick,
+    pub values: HashMap<Entity, C>,
+}
+
+/// Circular buffer of the server state of `C` for the last ticks, e.g. for lag compensation.
+///
+/// Only changed values are stored, lookups fall back to older ticks.
+#[derive(Resource)]
+pub struct ServerSnapshotHistory<C> {
+    pub snapshots: VecDeque<ServerSnapshot<C>>,
+    depth: usize,
+}
+
+impl<C> ServerSnapshotHistory<C> {
+    pub fn new(depth: usize) -> Self {
+        Self {
+            snapshots: VecDeque::with_capacity(depth),
+            depth,
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Returns the value of `entity` at `tick`, if it was captured within the history.
+    pub fn get_at_tick(&self, entity: Entity, tick: RepliconTick) -> Option<&C> {
+        self.snapshots
+            .iter()
+            .rev()
+            .skip_while(|snapshot| snapshot.tick > tick)
+            .find_map(|snapshot| snapshot.values.get(&entity))
+    }
+
+    fn push(&mut self, snapshot: ServerSnapshot<C>) {
+        if self.depth == 0 {
+            return;
+        }
+        while self.snapshots.len() >= self.depth {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+}
+
+/// Captures the values of `C` changed since the last server tick.
+pub fn server_snapshot_capture_system<C: Component + Clone>(
+    q_changed: Query<(Entity, &C), Changed<C>>,
+    mut history: ResMut<ServerSnapshotHistory<C>>,
+    tick: Res<ServerTick>,
+) {
+    history.push(ServerSnapshot {
+        tick: **tick,
+        values: q_changed
+            .iter()
+            .map(|(entity, component)| (entity, component.clone()))
+            .collect(),
+    });
+}
@@ -1,28 +1,125 @@
+// Systems naturally take many parameters and nested query types
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
 use std::fmt::Debug;
 
-use bevy::prelude::*;
+use bevy::{core::FrameCount, prelude::*};
 use bevy_replicon::prelude::*;
 use serde::{Deserialize, Serialize};
 
 pub use bevy_replicon_snap_macros;
 
 use crate::{
-    interpolation::{Interpolated, SnapshotInterpolationConfig},
-    prediction::{owner_prediction_init_system, OwnerPredicted, Predicted},
+    interpolation::{
+        ExtrapolationMode, Interpolated, InterpolationCulled, InterpolationCurve, InterpolationLod,
+        InterpolationOverride, SnapshotChannels, SnapshotGroup, SnapshotGroupRegistry,
+        SnapshotInterpolationConfig, SpriteFrameIndex,
+    },
+    prediction::{
+        estimate_server_tick_system, local_client_id_known, owner_prediction_init_system,
+        predicted_replay_system, prediction_not_paused, register_session_reset,
+        reset_estimated_server_tick, reset_session_system, resume_prediction_system,
+        EstimatedServerTick, OwnerPredicted, OwnershipChanged, Predicted, PredictionReset,
+        PredictionRollbackLimit,
+    },
+    spawn::{
+        confirm_predicted_spawns_system, predicted_despawn_timeout_system, server_despawn_system,
+    },
 };
 
+pub mod authority;
+mod bevy_types;
+pub mod format;
 pub mod interpolation;
+pub mod lag_compensation;
+#[cfg(feature = "lod")]
+pub mod lod;
 pub mod prediction;
+pub mod spawn;
 
+/// Entry point of the crate, configured with [`SnapshotInterpolationPlugin::new`].
+///
+/// The builder methods live on the separate [`SnapshotPlugin`] returned by `new`, so the public
+/// `max_tick_rate` field keeps working for existing code until it is removed with the next release.
 pub struct SnapshotInterpolationPlugin {
     /// Should reflect the server max tick rate
+    #[deprecated(note = "use `SnapshotInterpolationPlugin::new` instead")]
     pub max_tick_rate: u16,
 }
 
+impl SnapshotInterpolationPlugin {
+    /// Starts configuring the plugin, `max_tick_rate` should reflect the server max tick rate.
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(max_tick_rate: u16) -> SnapshotPlugin {
+        SnapshotPlugin::new(max_tick_rate)
+    }
+}
+
+/// Configurable plugin created with [`SnapshotInterpolationPlugin::new`].
+///
+/// Its settings are private, unlike the deprecated fields of [`SnapshotInterpolationPlugin`], so
+/// new settings can be added as builder methods without breaking changes.
+///
+/// There is intentionally no `with_adaptive_delay`, interpolated entities always trail the latest
+/// snapshot by one tick. Late snapshots are covered by [`SnapshotPlugin::with_extrapolation`].
+pub struct SnapshotPlugin {
+    config: SnapshotInterpolationConfig,
+    channels: SnapshotChannels,
+}
+
+impl SnapshotPlugin {
+    pub fn new(max_tick_rate: u16) -> Self {
+        Self {
+            config: SnapshotInterpolationConfig {
+                max_tick_rate,
+                curve: InterpolationCurve::default(),
+                max_snapshot_age_secs: 1.0,
+                max_snapshots: 2,
+                extrapolation: ExtrapolationMode::Disabled,
+            },
+            channels: SnapshotChannels::default(),
+        }
+    }
+
+    /// Blend curve applied to the interpolation factor between two snapshots.
+    pub fn with_curve(mut self, curve: InterpolationCurve) -> Self {
+        self.config.curve = curve;
+        self
+    }
+
+    /// Snapshots older than `max_snapshot_age_secs` are evicted, except for the latest one.
+    pub fn with_max_snapshot_age(mut self, max_snapshot_age_secs: f32) -> Self {
+        self.config.max_snapshot_age_secs = max_snapshot_age_secs;
+        self
+    }
+
+    /// Number of snapshots kept in each buffer, e.g. to look up older snapshots with
+    /// [`interpolation::SnapshotBuffer::get_at_tick`]. Defaults to 2.
+    pub fn with_max_snapshots(mut self, max_snapshots: usize) -> Self {
+        self.config.max_snapshots = max_snapshots;
+        self
+    }
+
+    /// Behavior of interpolated entities while the next snapshot is late, disabled by default.
+    pub fn with_extrapolation(mut self, extrapolation: ExtrapolationMode) -> Self {
+        self.config.extrapolation = extrapolation;
+        self
+    }
+
+    /// Settings for components registered with `replicate_interpolated_on_channel(channel_id)`.
+    pub fn with_channel(mut self, channel_id: u8, config: SnapshotInterpolationConfig) -> Self {
+        self.channels.0.insert(channel_id, config);
+        self
+    }
+}
+
 #[derive(Component, Deserialize, Serialize, Reflect)]
 pub struct NetworkOwner(pub u64);
 
 /// Sets for interpolation systems.
+///
+/// All systems in these sets only run while a client is connected, so dedicated servers and
+/// listen server hosts never create or grow [`interpolation::SnapshotBuffer`]s.
 #[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone, Copy)]
 pub enum InterpolationSet {
     /// Systems that initializes buffers and flag components for replicated entities.
@@ -33,30 +130,148 @@ pub enum InterpolationSet {
     ///
     /// Runs in `PreUpdate`.
     Interpolate,
+    /// Systems that need fully interpolated component values, e.g. camera follow.
+    ///
+    /// Runs in `PreUpdate`.
+    PostInterpolate,
+}
+
+/// Sets for prediction systems.
+///
+/// Run in `Update` in the listed order, so all reconciliation of a frame happens after the
+/// inputs of that frame were recorded. Order gameplay systems against them, e.g.
+/// `.after(PredictionSet::PostReconcile)` for cameras and animations that need corrected values.
+#[derive(SystemSet, Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum PredictionSet {
+    /// Systems that record local predicted events into their history.
+    RecordInput,
+    /// Server systems that apply the received inputs of all clients.
+    ApplyServer,
+    /// Systems that restore all predicted components and contexts to their latest server state.
+    Rollback,
+    /// Systems that replay the recorded events on top of the restored state.
+    Reconcile,
+    /// Systems that need the reconciled values, e.g. correction smoothing.
+    PostReconcile,
 }
 
 impl Plugin for SnapshotInterpolationPlugin {
     fn build(&self, app: &mut App) {
-        app.register_type::<Interpolated>()
+        #[allow(deprecated)]
+        SnapshotPlugin::new(self.max_tick_rate).build(app);
+    }
+}
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<SnapshotInterpolationConfig>()
+            .register_type::<InterpolationCurve>()
+            .register_type::<ExtrapolationMode>()
+            .register_type::<Interpolated>()
+            .register_type::<InterpolationLod>()
+            .register_type::<InterpolationCulled>()
+            .register_type::<InterpolationOverride>()
+            .register_type::<SpriteFrameIndex>()
+            .register_type::<SnapshotGroup>()
             .register_type::<OwnerPredicted>()
             .register_type::<NetworkOwner>()
             .register_type::<Predicted>()
             .replicate::<Interpolated>()
+            .replicate::<SnapshotGroup>()
             .replicate::<NetworkOwner>()
             .replicate::<OwnerPredicted>()
+            .add_event::<OwnershipChanged>()
+            .add_event::<PredictionReset>()
+            // Usually added by `FrameCountPlugin`, orders predicted events of different types
+            .init_resource::<FrameCount>()
+            .init_resource::<EstimatedServerTick>()
             .configure_sets(PreUpdate, InterpolationSet::Init.after(ClientSet::Receive))
             .configure_sets(
                 PreUpdate,
                 InterpolationSet::Interpolate.after(InterpolationSet::Init),
             )
+            .configure_sets(
+                PreUpdate,
+                InterpolationSet::PostInterpolate.after(InterpolationSet::Interpolate),
+            )
+            .configure_sets(
+                Update,
+                (
+                    PredictionSet::RecordInput,
+                    PredictionSet::ApplyServer,
+                    PredictionSet::Rollback,
+                    PredictionSet::Reconcile,
+                    PredictionSet::PostReconcile,
+                )
+                    .chain(),
+            )
+            .configure_sets(
+                Update,
+                PredictionSet::Rollback.run_if(prediction_not_paused),
+            )
+            .configure_sets(
+                Update,
+                PredictionSet::Reconcile.run_if(prediction_not_paused),
+            )
+            .configure_sets(
+                Update,
+                PredictionSet::PostReconcile.run_if(prediction_not_paused),
+            )
+            .add_systems(
+                PreUpdate,
+                estimate_server_tick_system
+                    .in_set(InterpolationSet::Init)
+                    .run_if(client_connected),
+            )
+            .add_systems(
+                PreUpdate,
+                reset_session_system
+                    .after(ClientSet::ReceivePackets)
+                    .before(ClientSet::Receive)
+                    .run_if(client_just_connected.or(client_just_disconnected)),
+            )
             .add_systems(
                 Update,
-                owner_prediction_init_system
-                    .run_if(client_connected)
-                    .in_set(InterpolationSet::Init),
+                (
+                    owner_prediction_init_system
+                        .in_set(InterpolationSet::Init)
+                        .run_if(
+                            client_connected
+                                .and(local_client_id_known)
+                                .or(server_or_singleplayer),
+                        ),
+                    (
+                        confirm_predicted_spawns_system,
+                        predicted_despawn_timeout_system,
+                    )
+                        .run_if(client_connected),
+                    server_despawn_system.run_if(server_or_singleplayer),
+                    resume_prediction_system
+                        .in_set(PredictionSet::RecordInput)
+                        .run_if(client_connected),
+                    predicted_replay_system
+                        .in_set(PredictionSet::Reconcile)
+                        .run_if(client_connected),
+                ),
             )
-            .insert_resource(SnapshotInterpolationConfig {
-                max_tick_rate: self.max_tick_rate,
-            });
+            .insert_resource(PredictionRollbackLimit {
+                max_ticks: 2 * self.config.max_tick_rate as u32,
+            })
+            .insert_resource(self.channels.clone())
+            .init_resource::<SnapshotGroupRegistry>()
+            .insert_resource(self.config.clone());
+        register_session_reset(app, reset_estimated_server_tick);
+
+        #[cfg(feature = "lod")]
+        app.init_resource::<lod::InterpolationLodDistances>()
+            .add_systems(
+                PreUpdate,
+                (
+                    lod::interpolation_lod_update_system,
+                    lod::interpolation_frustum_cull_system,
+                )
+                    .in_set(InterpolationSet::Init)
+                    .run_if(client_connected),
+            );
     }
 }
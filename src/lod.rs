@@ -0,0 +1,113 @@
+use bevy::{
+    ecs::{
+        change_detection::DetectChangesMut,
+        entity::Entity,
+        query::{Has, With},
+        system::{Commands, Query, Res, Resource},
+    },
+    render::{
+        camera::Camera,
+        primitives::{Aabb, Frustum},
+    },
+    transform::components::GlobalTransform,
+};
+
+use crate::interpolation::{Interpolated, InterpolationCulled, InterpolationLod};
+
+/// Camera distances at which [`interpolation_lod_update_system`] lowers the [`InterpolationLod`].
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct InterpolationLodDistances {
+    pub medium: f32,
+    pub low: f32,
+    pub disabled: f32,
+}
+
+impl Default for InterpolationLodDistances {
+    fn default() -> Self {
+        Self {
+            medium: 50.0,
+            low: 150.0,
+            disabled: 400.0,
+        }
+    }
+}
+
+impl InterpolationLodDistances {
+    /// Level of detail for an entity at `distance` from the closest camera.
+    pub fn lod_at(&self, distance: f32) -> InterpolationLod {
+        if distance >= self.disabled {
+            InterpolationLod::Disabled
+        } else if distance >= self.low {
+            InterpolationLod::Low
+        } else if distance >= self.medium {
+            InterpolationLod::Medium
+        } else {
+            InterpolationLod::High
+        }
+    }
+}
+
+/// Updates the [`InterpolationLod`] of interpolated entities from their distance to the closest
+/// active camera. Only entities that have an [`InterpolationLod`] are considered.
+pub fn interpolation_lod_update_system(
+    q_cameras: Query<(&Camera, &GlobalTransform)>,
+    mut q_interpolated: Query<(&GlobalTransform, &mut InterpolationLod), With<Interpolated>>,
+    distances: Res<InterpolationLodDistances>,
+) {
+    for (transform, mut lod) in &mut q_interpolated {
+        let Some(distance) = q_cameras
+            .iter()
+            .filter(|(camera, _)| camera.is_active)
+            .map(|(_, camera_transform)| {
+                camera_transform
+                    .translation()
+                    .distance(transform.translation())
+            })
+            .reduce(f32::min)
+        else {
+            continue;
+        };
+        lod.set_if_neq(distances.lod_at(distance));
+    }
+}
+
+/// Insert to stop interpolating entities outside the frustum of every active camera, see
+/// [`interpolation_frustum_cull_system`]. Only entities with an [`Aabb`] are culled.
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct InterpolationFrustumCull;
+
+/// Marks interpolated entities outside the frustum of every active camera with
+/// [`InterpolationCulled`] while [`InterpolationFrustumCull`] exists.
+pub fn interpolation_frustum_cull_system(
+    cull: Option<Res<InterpolationFrustumCull>>,
+    q_cameras: Query<(&Camera, &Frustum)>,
+    q_interpolated: Query<
+        (Entity, &Aabb, &GlobalTransform, Has<InterpolationCulled>),
+        With<Interpolated>,
+    >,
+    q_culled: Query<Entity, With<InterpolationCulled>>,
+    mut commands: Commands,
+) {
+    let frusta: Vec<&Frustum> = q_cameras
+        .iter()
+        .filter(|(camera, _)| camera.is_active)
+        .map(|(_, frustum)| frustum)
+        .collect();
+    // Without a camera there is nothing to cull against
+    if cull.is_none() || frusta.is_empty() {
+        for entity in &q_culled {
+            commands.entity(entity).remove::<InterpolationCulled>();
+        }
+        return;
+    }
+    for (entity, aabb, transform, culled) in &q_interpolated {
+        let visible = frusta
+            .iter()
+            .any(|frustum| frustum.intersects_obb(aabb, &transform.affine(), true, false));
+        if visible && culled {
+            commands.entity(entity).remove::<InterpolationCulled>();
+        } else if !visible && !culled {
+            commands.entity(entity).insert(InterpolationCulled);
+        }
+    }
+}
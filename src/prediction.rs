@@ -1,184 +1,665 @@
 use bevy::{
-    app::{App, Update},
+    app::{App, PreUpdate, Update},
     ecs::{
-        component::Component,
-        entity::Entity,
-        event::{Event, EventReader},
-        query::{Added, With, Without},
-        schedule::IntoSystemConfigs,
-        system::{Commands, Query, Res, ResMut, Resource},
+        bundle::Bundle,
+        component::{Component, ComponentId},
+        entity::{Entity, MapEntities},
+        event::Event,
+        query::{QueryItem, ReadOnlyQueryData, With, Without},
+        removal_detection::RemovedComponents,
+        schedule::{common_conditions::resource_exists, IntoSystemConfigs},
+        system::{Commands, Local, Query, Res, Resource},
+        world::{DeferredWorld, World},
     },
     reflect::Reflect,
     time::Time,
+    utils::HashSet,
 };
 use bevy_replicon::{
+    bincode,
     client::confirm_history::ConfirmHistory,
     core::{
-        channels::RepliconChannel, common_conditions::client_connected,
-        replicon_client::RepliconClient,
+        channels::RepliconChannel,
+        common_conditions::client_connected,
+        replication::{
+            deferred_entity::DeferredEntity,
+            replication_registry::{
+                ctx::{RemoveCtx, WriteCtx},
+                rule_fns::RuleFns,
+            },
+        },
+        replicon_tick::RepliconTick,
+    },
+    prelude::{
+        server_or_singleplayer, AppMarkerExt, AppRuleExt, ChannelKind, ClientEventAppExt,
+        FromClient, ServerEventAppExt,
     },
-    prelude::{server_or_singleplayer, AppRuleExt, ClientEventAppExt, FromClient},
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use std::collections::vec_deque::Iter;
-use std::collections::VecDeque;
+use std::any::{type_name, TypeId};
 use std::fmt::Debug;
+use std::io::Cursor;
+use std::marker::PhantomData;
 
 use crate::{
-    interpolation::Interpolate, interpolation::SnapshotBuffer, Interpolated, NetworkOwner,
+    interpolation::{
+        register_record_snapshots_marker, Interpolate, RecordSnapshotsMarker, SnapshotBuffer,
+    },
+    Interpolated, InterpolationSet, PredictionSet,
+};
+
+mod correction;
+mod history;
+mod input;
+mod ownership;
+mod remote;
+mod replay;
+mod resource;
+mod server;
+mod state;
+mod tick;
+
+pub use correction::{
+    prediction_correction_system, transition_blend_init_system, transition_blend_system,
+    transition_restore_system, CorrectionMagnitude, CorrectionTolerance, PredictionCorrected,
+    PredictionCorrectionBudget, PredictionError, PredictionSmoothing, PredictionTransition,
+    TransitionBlend, CORRECTION_EPSILON,
+};
+use history::sequence_is_newer;
+pub use history::{
+    dump_prediction_history, EventSnapshot, PredictedEventConfig, PredictedEventHistory,
+};
+pub use input::{
+    coalesce_predicted_inputs_system, drain_predicted_inputs_system,
+    expire_unacknowledged_inputs_system, queue_predicted_inputs_system, receive_input_acks_system,
+    record_predicted_inputs_system, send_input_acks_system, send_predicted_inputs_system, InputAck,
+    InputCoalescing, PredictedInput, PredictedInputBatch, ServerInputQueue,
+};
+pub use ownership::{
+    clear_history_on_ownership_change_system, local_client_id, local_client_id_known,
+    owner_prediction_init_system, LocalClientId, OwnershipChanged,
+};
+pub use remote::{
+    remote_prediction_system, server_remote_input_system, RemoteInput, RemotePredicted,
 };
+use replay::{
+    divergence_begin, divergence_end, divergence_restore, divergence_save, replay_event,
+    replay_event_multi, replay_event_query, reset_event_spawns, spawn_event_entity,
+    timeline_acknowledged, timeline_first_new, timeline_frames, timeline_pending_secs,
+    timeline_replay, timeline_reset, timeline_trim, DivergenceBackup, DivergenceCheck,
+    DivergenceTrace, EventTimeline, PredictedEventSpawns,
+};
+pub use replay::{
+    predicted_replay_system, rollback_component_system, rollback_context_system,
+    PredictionDivergence, PredictionDivergenceLog,
+};
+use resource::reset_resource_snapshot;
+pub use resource::{
+    predicted_resource_update_system, resource_snapshot_system, server_resource_update_system,
+    ResourceSnapshotBuffer,
+};
+pub use server::{
+    server_correction_system, server_query_update_system, server_update_multi_system,
+    server_update_system,
+};
+pub use state::{
+    predicted_state_system, server_state_prediction_system, StatePrediction, StatePredictionSteps,
+};
+pub(crate) use tick::reset_estimated_server_tick;
+pub use tick::{estimate_server_tick_system, EstimatedServerTick};
+
+/// Information about a single application of a predicted event.
+#[derive(Clone, Copy, Debug)]
+pub struct PredictInfo {
+    /// Tick the event was recorded at on the client, identical on client and server.
+    pub tick: RepliconTick,
+    /// Frame time the event was recorded with.
+    pub delta_time: f32,
+    /// `true` when the client replays an event it already applied, e.g. to suppress sounds.
+    pub is_resimulation: bool,
+    /// Input sequence of the event, identical on client and server. Always `0` for
+    /// [`RemotePredicted`] entities.
+    pub sequence: u32,
+}
 
 /// This trait defines how an event will mutate a given component
 /// and is required for prediction.
+///
+/// The context can be mutated as well, e.g. to spend stamina. On the client it is restored
+/// to its latest server state before inputs are replayed, so mutations are not applied twice.
+/// [`RemotePredicted`] entities get a copy of their context, it keeps the replicated value.
 pub trait Predict<E: Event, T>
 where
     Self: Component + Interpolate,
 {
-    fn apply_event(&mut self, event: &E, delta_time: f32, context: &T);
+    fn apply_event(&mut self, event: &E, info: &PredictInfo, context: &mut T);
+
+    /// How severe the correction from this predicted value to the `server` value is.
+    ///
+    /// With a [`PredictionCorrectionBudget`] the most severe corrections are applied first.
+    fn correction_magnitude(&self, _server: &Self) -> f32 {
+        f32::INFINITY
+    }
 }
 
-pub struct EventSnapshot<T: Event> {
-    pub value: T,
-    pub tick: u32,
-    pub delta_time: f32,
+/// Spawns at most one entity per predicted event, e.g. a construction ghost or a projectile.
+///
+/// Called right after [`Predict::apply_event`] with the updated value, on the server for every
+/// applied input and on the client for every replay. The client keeps a single entity per event
+/// across replays and updates it with the bundle of the latest replay. It is despawned once a
+/// rollback no longer produces it or the event is reflected in the server state, where the
+/// entity spawned by the server replicates instead.
+///
+/// Register with [`AppPredictionExt::predict_event_for_component_with_spawn`].
+pub trait PredictEventSpawn<E: Event, T>
+where
+    Self: Predict<E, T>,
+{
+    type Spawn: Bundle;
+
+    fn spawn_for_event(&self, event: &E, info: &PredictInfo, context: &T) -> Option<Self::Spawn>;
 }
 
-#[derive(Resource)]
-pub struct PredictedEventHistory<T: Event>(pub VecDeque<EventSnapshot<T>>);
+/// Context-free variant of [`Predict`] for events that only need the component itself.
+///
+/// Register with [`AppPredictionExt::predict_event_for_component_simple`].
+pub trait PredictSimple<E: Event>
+where
+    Self: Component + Interpolate,
+{
+    fn apply_event(&mut self, event: &E, info: &PredictInfo);
+}
+
+/// Variant of [`Predict`] that reads several components of the same entity instead of a single
+/// context component, e.g. `(&Stats, &Collider)`.
+///
+/// The server and the client replays fetch the same read-only query data `Q`, which must not
+/// include the predicted component itself.
+///
+/// Register with [`AppPredictionExt::predict_event_for_component_with_query`].
+pub trait PredictQuery<E: Event, Q: ReadOnlyQueryData>
+where
+    Self: Component + Interpolate,
+{
+    fn apply_event(&mut self, event: &E, info: &PredictInfo, query: QueryItem<'_, Q>);
+}
+
+/// Empty prediction context used by [`PredictSimple`] registrations.
+///
+/// Automatically required by components registered with
+/// [`AppPredictionExt::predict_event_for_component_simple`] and never replicated.
+#[derive(Component, Deserialize, Serialize, Reflect, Default, Clone, Copy)]
+pub struct NoContext;
+
+impl<E: Event, C: PredictSimple<E>> Predict<E, NoContext> for C {
+    fn apply_event(&mut self, event: &E, info: &PredictInfo, _context: &mut NoContext) {
+        PredictSimple::apply_event(self, event, info);
+    }
+}
+
+/// Mutable access to both components of a [`PredictMulti`] prediction.
+pub struct PredictMultiInput<'a, C1, C2> {
+    pub first: &'a mut C1,
+    pub second: &'a mut C2,
+}
+
+/// Variant of [`Predict`] for events that mutate two components atomically,
+/// e.g. consuming stamina while moving.
+///
+/// Implemented on the first component, both components are rolled back to their snapshots
+/// before inputs are replayed. Register with [`AppPredictionExt::predict_event_for_components`].
+pub trait PredictMulti<E: Event, C2: Component + Interpolate, T>
+where
+    Self: Component + Interpolate + Sized,
+{
+    fn apply_event(
+        input: PredictMultiInput<'_, Self, C2>,
+        event: &E,
+        info: &PredictInfo,
+        context: &mut T,
+    );
+}
+
+/// Variant of [`Predict`] for events that mutate a resource, e.g. a shared mana pool.
+///
+/// Register with [`AppPredictionExt::predict_event_for_resource`].
+pub trait PredictResource<E: Event>
+where
+    Self: Resource,
+{
+    fn apply_event(&mut self, event: &E, info: &PredictInfo);
+}
+
+/// A server-to-client event carrying the full corrected state of a component.
+///
+/// Register with [`AppPredictionExt::add_server_corrected_event`].
+pub trait ServerCorrection: Event + MapEntities {
+    type Component: Component + Interpolate + Clone;
+
+    /// Entity the correction applies to.
+    fn entity(&self) -> Entity;
+
+    /// Corrected state of the component.
+    fn corrected(&self) -> Self::Component;
+}
 
 #[derive(Component, Deserialize, Serialize, Reflect)]
 pub struct OwnerPredicted;
 
+/// Marks entities whose components are predicted by this client.
+///
+/// Mutually exclusive with [`Interpolated`], inserting it removes [`Interpolated`], also when both
+/// are inserted during the same tick.
 #[derive(Component, Reflect)]
+#[component(on_add = on_predicted_added)]
 pub struct Predicted;
 
-impl<T: Event> PredictedEventHistory<T> {
-    pub fn new() -> PredictedEventHistory<T> {
-        Self(VecDeque::new())
-    }
-    pub fn insert(&mut self, value: T, tick: u32, delta_time: f32) -> &mut Self {
-        self.0.push_back(EventSnapshot {
-            value,
-            tick,
-            delta_time,
-        });
-        self
-    }
-    pub fn remove_stale(&mut self, latest_server_snapshot_tick: u32) -> &mut Self {
-        if let Some(last_index) = self
-            .0
-            .iter()
-            .position(|v| v.tick >= latest_server_snapshot_tick)
-        {
-            self.0.drain(0..last_index);
-        } else {
-            self.0.clear();
+fn on_predicted_added(mut world: DeferredWorld, entity: Entity, _component_id: ComponentId) {
+    world
+        .commands()
+        .entity(entity)
+        .remove::<(Interpolated, Reconciled)>();
+}
+
+/// Confirmed tick a predicted entity was last rolled back to.
+///
+/// Until a newer tick is confirmed, only the inputs of the current frame are applied on top of
+/// the current values instead of rolling back and replaying the whole history.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct Reconciled {
+    pub tick: RepliconTick,
+    /// Whether the entity was rolled back during the current frame.
+    pub rolled_back: bool,
+}
+
+/// Whether the entity received a snapshot since it was last rolled back. Entities without a
+/// [`ConfirmHistory`] are always rolled back.
+fn needs_rollback(confirmed: Option<&ConfirmHistory>, reconciled: Option<&Reconciled>) -> bool {
+    match (confirmed, reconciled) {
+        (Some(confirmed), Some(reconciled)) => confirmed.last_tick() != reconciled.tick,
+        _ => true,
+    }
+}
+
+/// Latest server state of the prediction context `T` of a predicted entity.
+///
+/// Replays start from this value instead of the locally mutated context.
+#[derive(Component)]
+pub struct ContextSnapshot<T: Component>(pub T);
+
+/// Prediction of `C` from before the latest rollback, continued with the events recorded since.
+///
+/// Compared against the reconciled value to detect corrections.
+#[derive(Component)]
+pub struct PredictionState<C: Component> {
+    pub uncorrected: C,
+}
+
+/// Replays a single event on one predicted registration of an entity.
+type ReplayFn<E> = fn(&mut World, Entity, &E, &PredictInfo);
+
+/// Components predicted by the event `E`, in registration order.
+#[derive(Resource)]
+pub struct PredictedComponents<E: Event> {
+    replays: Vec<ReplayFn<E>>,
+}
+
+impl<E: Event> Default for PredictedComponents<E> {
+    fn default() -> Self {
+        Self {
+            replays: Vec::new(),
         }
-        self
     }
+}
+
+/// Component and context types that already have their rollback systems registered,
+/// the predicted events in registration order and the cleanups that run when the client
+/// connects or disconnects.
+#[derive(Resource, Default)]
+pub(crate) struct PredictionRegistry {
+    components: HashSet<TypeId>,
+    contexts: HashSet<TypeId>,
+    remote_inputs: HashSet<TypeId>,
+    timelines: Vec<EventTimeline>,
+    divergence_checks: Vec<DivergenceCheck>,
+    divergence_backups: Vec<DivergenceBackup>,
+    session_resets: Vec<fn(&mut World)>,
+}
+
+/// Registers a cleanup of client state that must not outlive a connection.
+pub(crate) fn register_session_reset(app: &mut App, reset: fn(&mut World)) {
+    app.world_mut()
+        .get_resource_or_insert_with(PredictionRegistry::default)
+        .session_resets
+        .push(reset);
+}
+
+/// Panics with a descriptive message if `E` was not registered with
+/// [`AppPredictionExt::add_client_predicted_event`], its history would be missing at runtime.
+fn assert_predicted_event_registered<E: Event>(app: &App) {
+    assert!(
+        app.world().contains_resource::<PredictedComponents<E>>(),
+        "{} must be registered with `add_client_predicted_event` before it is used for prediction",
+        type_name::<E>()
+    );
+}
+
+/// Removes `B` from every entity.
+pub(crate) fn remove_from_all<B: Bundle>(world: &mut World) {
+    let entities: Vec<Entity> = world.query::<Entity>().iter(world).collect();
+    for entity in entities {
+        world.entity_mut(entity).remove::<B>();
+    }
+}
 
-    pub fn predict(&mut self, latest_server_snapshot_tick: u32) -> Iter<'_, EventSnapshot<T>> {
-        self.remove_stale(latest_server_snapshot_tick);
-        self.0.iter()
+fn clear_history<E: Event>(world: &mut World) {
+    let mut history = world.resource_mut::<PredictedEventHistory<E>>();
+    history.clear();
+    history.acknowledged = None;
+}
+
+/// Drops all client prediction and interpolation state of the previous connection,
+/// so a new session does not replay inputs or snapshots stamped with old ticks.
+pub fn reset_session_system(world: &mut World) {
+    let session_resets = world
+        .get_resource::<PredictionRegistry>()
+        .map(|registry| registry.session_resets.clone())
+        .unwrap_or_default();
+    for reset in session_resets {
+        reset(world);
     }
+    remove_from_all::<(Predicted, Interpolated, RecordSnapshotsMarker)>(world);
+}
+
+/// Pauses client prediction, e.g. while a menu is open.
+///
+/// As a resource no new events are recorded and the rollback, replay and correction systems
+/// don't run, as a component only the entity is neither rolled back nor replayed. Once removed,
+/// the entities roll back to their latest snapshot and replay the remaining events.
+#[derive(Resource, Component, Clone, Copy, Debug, Default)]
+pub struct PredictionPaused;
+
+/// Run condition that returns `true` unless the [`PredictionPaused`] resource exists.
+pub fn prediction_not_paused(paused: Option<Res<PredictionPaused>>) -> bool {
+    paused.is_none()
 }
 
-pub fn owner_prediction_init_system(
-    q_owners: Query<(Entity, &NetworkOwner), Added<OwnerPredicted>>,
-    client: Res<RepliconClient>,
+/// Resynchronizes entities from their latest snapshot once prediction is resumed, globally or
+/// for a single entity.
+pub fn resume_prediction_system(
+    paused: Option<Res<PredictionPaused>>,
+    mut was_paused: Local<bool>,
+    mut resumed: RemovedComponents<PredictionPaused>,
+    q_reconciled: Query<Entity, With<Reconciled>>,
     mut commands: Commands,
 ) {
-    let client_id = client.id().expect("No client id id found");
-    for (e, id) in q_owners.iter() {
-        if id.0 == client_id.get() {
-            commands.entity(e).insert(Predicted);
-        } else {
-            commands.entity(e).insert(Interpolated);
-        }
+    let resumed_globally = *was_paused && paused.is_none();
+    *was_paused = paused.is_some();
+    let resumed: Vec<Entity> = if resumed_globally {
+        resumed.clear();
+        q_reconciled.iter().collect()
+    } else {
+        resumed
+            .read()
+            .filter(|&entity| q_reconciled.contains(entity))
+            .collect()
+    };
+    for entity in resumed {
+        commands.entity(entity).remove::<Reconciled>();
     }
 }
 
+/// Maximum number of ticks of inputs replayed in a single frame, e.g. after a connectivity
+/// hiccup. Inserted by the plugin with twice the tick rate, remove it to replay any amount.
+///
+/// Once the unconfirmed inputs cover a longer time, all histories are dropped, predicted
+/// entities snap to their latest snapshot and [`PredictionReset`] is sent for each of them.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct PredictionRollbackLimit {
+    pub max_ticks: u32,
+}
+
+/// Sent on the client for every predicted entity that snapped to its latest snapshot because
+/// of the [`PredictionRollbackLimit`], e.g. to mask the jump with a fade or camera smoothing.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PredictionReset {
+    pub entity: Entity,
+}
+
+/// Server side validation of `E` before it is applied to `C`, registered with
+/// [`AppPredictionExt::predict_event_for_component_with_validation`].
+///
+/// Returns the event to apply, e.g. with a clamped speed, or `None` to reject it.
+#[derive(Resource)]
+pub struct EventValidation<E, T, C>(pub fn(&E, &C, &T) -> Option<E>);
+
+/// Spawns the entities of [`PredictEventSpawn`] for `C`, registered with
+/// [`AppPredictionExt::predict_event_for_component_with_spawn`].
+///
+/// Inserts the bundle into the existing entity of the event if there is one.
+#[derive(Resource)]
+pub struct EventSpawner<E, T, C>(
+    fn(&C, &E, &PredictInfo, &T, &mut Commands, Option<Entity>) -> Option<Entity>,
+);
+
 /// Advances the snapshot buffer time for predicted entities.
 pub fn predicted_snapshot_system<T: Component + Interpolate + Clone>(
     mut q: Query<&mut SnapshotBuffer<T>, (Without<Interpolated>, With<Predicted>)>,
     time: Res<Time>,
 ) {
     for mut snapshot_buffer in q.iter_mut() {
-        snapshot_buffer.time_since_last_snapshot += time.delta_secs();
+        snapshot_buffer.advance(time.delta_secs());
     }
 }
 
-/// Server implementation
-pub fn server_update_system<
-    E: Event,
-    T: Component,
-    C: Component + Interpolate + Predict<E, T> + Clone,
->(
-    time: Res<Time>,
-    mut move_events: EventReader<FromClient<E>>,
-    mut subjects: Query<(&NetworkOwner, &mut C, &T), Without<Predicted>>,
-) {
-    for FromClient { client_id, event } in move_events.read() {
-        for (player, mut component, context) in &mut subjects {
-            if client_id.get() == player.0 {
-                component.apply_event(event, time.delta_secs(), context);
-            }
-        }
-    }
+/// Newest tick confirmed for any predicted entity, compared wrapping-aware.
+fn latest_confirmed_tick(
+    q_predicted: &Query<&ConfirmHistory, With<Predicted>>,
+) -> Option<RepliconTick> {
+    q_predicted
+        .iter()
+        .map(|confirmed| confirmed.last_tick())
+        .reduce(|latest, tick| if tick > latest { tick } else { latest })
 }
 
-// Client prediction implementation
-pub fn predicted_update_system<
-    E: Event + Clone,
-    T: Component,
-    C: Component + Interpolate + Predict<E, T> + Clone,
->(
-    mut q_predicted_players: Query<
-        (&mut C, &SnapshotBuffer<C>, &ConfirmHistory, &T),
-        (With<Predicted>, Without<Interpolated>),
-    >,
-    mut local_events: EventReader<E>,
-    mut event_history: ResMut<PredictedEventHistory<E>>,
-    time: Res<Time>,
-) {
-    // Apply all pending inputs to latest snapshot
-    for (mut component, snapshot_buffer, confirmed, context) in q_predicted_players.iter_mut() {
-        // Append the latest input event
-        for event in local_events.read() {
-            event_history.insert(
-                event.clone(),
-                confirmed.last_tick().get(),
-                time.delta_secs(),
-            );
-        }
-
-        let mut corrected_component = snapshot_buffer.latest_snapshot();
-        for event_snapshot in event_history.predict(snapshot_buffer.latest_snapshot_tick()) {
-            corrected_component.apply_event(
-                &event_snapshot.value,
-                event_snapshot.delta_time,
-                context,
-            );
+/// Writes the received prediction context into [`ContextSnapshot`].
+///
+/// The context component itself is only overwritten for entities that are not predicted,
+/// predicted entities derive it from the snapshot when replaying inputs.
+fn write_context_component<T: Component + Clone + DeserializeOwned>(
+    ctx: &mut WriteCtx,
+    rule_fns: &RuleFns<T>,
+    entity: &mut DeferredEntity,
+    cursor: &mut Cursor<&[u8]>,
+) -> bincode::Result<()> {
+    let context: T = rule_fns.deserialize(ctx, cursor)?;
+    if !entity.contains::<Predicted>() {
+        if let Some(mut component) = entity.get_mut::<T>() {
+            *component = context.clone();
+        } else {
+            ctx.commands.entity(entity.id()).insert(context.clone());
         }
-        *component = corrected_component;
     }
+    ctx.commands
+        .entity(entity.id())
+        .insert(ContextSnapshot(context));
+
+    Ok(())
+}
+
+fn remove_context_component<T: Component>(ctx: &mut RemoveCtx, entity: &mut DeferredEntity) {
+    ctx.commands
+        .entity(entity.id())
+        .remove::<ContextSnapshot<T>>()
+        .remove::<T>();
 }
 
 pub trait AppPredictionExt {
     /// Register an event for client-side prediction, this will make sure a history of past events
     /// is stored for the client to be able to replay them in case of a server correction
+    ///
+    /// A [`PredictedEventHistory`] of `E` inserted before, e.g. with `init_resource`, is kept
+    /// together with its config.
     fn add_client_predicted_event<E>(&mut self, channel: impl Into<RepliconChannel>) -> &mut Self
     where
         E: Event + Serialize + DeserializeOwned + Debug + Clone;
 
+    /// Same as [`AppPredictionExt::add_client_predicted_event`], but with custom history limits
+    /// for this event type.
+    fn add_client_predicted_event_with_config<E>(
+        &mut self,
+        channel: impl Into<RepliconChannel>,
+        config: PredictedEventConfig,
+    ) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone;
+
+    /// Same as [`AppPredictionExt::add_client_predicted_event`], but inputs are sent on a reliable
+    /// ordered channel and never repeated.
+    fn add_client_predicted_event_reliable<E>(&mut self) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone;
+
+    /// Same as [`AppPredictionExt::add_client_predicted_event`], but inputs are sent on an
+    /// unreliable channel. Inputs the server does not acknowledge within `ack_timeout` seconds
+    /// are considered lost and removed from the history instead of being replayed indefinitely.
+    fn add_client_predicted_event_unreliable_with_ack<E>(&mut self, ack_timeout: f32) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone;
+
+    /// Same as [`AppPredictionExt::add_client_predicted_event`], but inputs recorded within one
+    /// estimated server tick are merged with `merge` before they are sent, e.g. held movement
+    /// inputs of a client running at a higher frame rate than the server tick rate.
+    ///
+    /// The merged input is applied with the summed delta time, so `merge` should keep the
+    /// prediction of the single inputs, e.g. by keeping the latest direction.
+    fn add_client_predicted_event_coalesced<E>(
+        &mut self,
+        channel: impl Into<RepliconChannel>,
+        merge: fn(&E, &E) -> E,
+    ) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone;
+
     /// Register a component and event pair for prediction.
     /// This will generate serverside and clientside systems that use the implementation from the
-    /// `Predict` trait to allow prediction and serverside correction
+    /// `Predict` trait to allow prediction and serverside correction.
+    /// `C` needs to be registered with `replicate_interpolated` and `E` with
+    /// [`AppPredictionExt::add_client_predicted_event`] before calling this, otherwise it panics.
+    ///
+    /// The context `T` is replicated by the first registration that uses it, use
+    /// [`AppPredictionExt::predict_event_for_component_no_replicate`] if `T` is already
+    /// replicated elsewhere.
     fn predict_event_for_component<E, T, C>(&mut self) -> &mut Self
     where
         E: Event + Serialize + DeserializeOwned + Debug + Clone,
-        T: Component + Serialize + DeserializeOwned,
+        T: Component + Serialize + DeserializeOwned + Clone,
+        C: Component + Predict<E, T> + Clone;
+
+    /// Same as [`AppPredictionExt::predict_event_for_component`], but the context `T` is not
+    /// replicated by this crate. Call `replicate::<T>()` yourself, e.g. with custom rules.
+    fn predict_event_for_component_no_replicate<E, T, C>(&mut self) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone,
+        T: Component + Serialize + DeserializeOwned + Clone,
+        C: Component + Predict<E, T> + Clone;
+
+    /// Same as [`AppPredictionExt::predict_event_for_component`], but every input is passed to
+    /// `validate` on the server before it is applied. The hook can clamp the event or reject it by
+    /// returning `None`, e.g. to enforce speed caps. Clients still predict the raw event and are
+    /// corrected by the server state.
+    fn predict_event_for_component_with_validation<E, T, C>(
+        &mut self,
+        validate: fn(&E, &C, &T) -> Option<E>,
+    ) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone,
+        T: Component + Serialize + DeserializeOwned + Clone,
+        C: Component + Predict<E, T> + Clone;
+
+    /// Same as [`AppPredictionExt::predict_event_for_component`], but every application of `E`
+    /// can spawn an entity through [`PredictEventSpawn`], e.g. a building placed by the input.
+    fn predict_event_for_component_with_spawn<E, T, C>(&mut self) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone,
+        T: Component + Serialize + DeserializeOwned + Clone,
+        C: Component + PredictEventSpawn<E, T> + Clone;
+
+    /// Same as [`AppPredictionExt::predict_event_for_component`], but for components that
+    /// implement [`PredictSimple`] and need no context component.
+    fn predict_event_for_component_simple<E, C>(&mut self) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone,
+        C: Component + PredictSimple<E> + Clone;
+
+    /// Same as [`AppPredictionExt::predict_event_for_component`], but for components that
+    /// implement [`PredictQuery`] and read the components in `Q` instead of a context component,
+    /// e.g. `predict_event_for_component_with_query::<E, (&Stats, &Collider), C>()`.
+    /// The components in `Q` are not replicated by this call.
+    fn predict_event_for_component_with_query<E, Q, C>(&mut self) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone,
+        Q: ReadOnlyQueryData + 'static,
+        C: Component + PredictQuery<E, Q> + Clone;
+
+    /// Register a server event that explicitly corrects a component with [`ServerCorrection`].
+    /// On the client the corrected state replaces the latest snapshot before the next interpolation
+    /// and prediction pass.
+    fn add_server_corrected_event<E>(&mut self, channel: impl Into<RepliconChannel>) -> &mut Self
+    where
+        E: ServerCorrection + Serialize + DeserializeOwned + Clone;
+
+    /// Register an event that mutates two components together with [`PredictMulti`].
+    /// Both `C1` and `C2` need to be registered with `replicate_interpolated` before calling this.
+    fn predict_event_for_components<E, T, C1, C2>(&mut self) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone,
+        T: Component + Serialize + DeserializeOwned + Clone,
+        C1: Component + PredictMulti<E, C2, T> + Clone,
+        C2: Component + Interpolate + Clone;
+
+    /// Corrections of `C` up to `tolerance`, measured with [`Interpolate::distance`], keep the
+    /// client's predicted value instead of replacing it with the replayed one.
+    /// Avoids jitter from floating point drift, larger corrections are not affected.
+    fn set_correction_tolerance<C>(&mut self, tolerance: f32) -> &mut Self
+    where
+        C: Component + Interpolate;
+
+    /// Register a component that is predicted from its own state instead of events,
+    /// e.g. to keep moving with its current velocity.
+    ///
+    /// `f` returns the next value from the current value and the frame delta. It runs every frame
+    /// on the server and on [`Predicted`] entities and is reconciled against server snapshots like
+    /// event driven predictions. `C` needs to be registered with `replicate_interpolated` before
+    /// calling this.
+    fn predict_component_from_state<C, F>(&mut self, f: F) -> &mut Self
+    where
+        C: Component + Interpolate + Clone,
+        F: Fn(&C, f32) -> C + Send + Sync + 'static;
+
+    /// Advance `C` of [`Interpolated`] entities owned by other clients with their latest input
+    /// of `E`, instead of interpolating it one tick behind.
+    ///
+    /// The server replicates the latest input of every owner as [`RemoteInput`], clients apply it
+    /// through [`Predict`] between snapshots and restart from every new snapshot. The context `T`
+    /// is passed as a copy, so it keeps its replicated value. `E`, `T` and `C` need to be
+    /// registered with `predict_event_for_component` and `C` with `replicate_interpolated` before
+    /// calling this.
+    fn predict_remote_entities<E, T, C>(&mut self) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone,
+        T: Component + Clone,
         C: Component + Predict<E, T> + Clone;
+
+    /// Register a resource and event pair for prediction with [`PredictResource`].
+    /// `E` needs to be registered with `add_client_predicted_event` before calling this.
+    ///
+    /// Resources are not replicated as components, the server state is captured in
+    /// [`ResourceSnapshotBuffer`] whenever `R` changes outside of the prediction.
+    fn predict_event_for_resource<E, R>(&mut self) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone,
+        R: Resource + PredictResource<E> + Clone;
 }
 
 impl AppPredictionExt for App {
@@ -186,24 +667,446 @@ impl AppPredictionExt for App {
     where
         E: Event + Serialize + DeserializeOwned + Debug + Clone,
     {
-        let history: PredictedEventHistory<E> = PredictedEventHistory::new();
-        self.insert_resource(history);
-        self.add_client_event::<E>(channel)
+        self.add_client_predicted_event_with_config::<E>(channel, PredictedEventConfig::default())
+    }
+
+    fn add_client_predicted_event_with_config<E>(
+        &mut self,
+        channel: impl Into<RepliconChannel>,
+        config: PredictedEventConfig,
+    ) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone,
+    {
+        // Keeps a history inserted before the registration, e.g. with `init_resource`
+        if !self.world().contains_resource::<PredictedEventHistory<E>>() {
+            self.insert_resource(PredictedEventHistory::<E>::with_config(config));
+        }
+        self.world_mut()
+            .get_resource_or_insert_with(PredictedComponents::<E>::default);
+        register_session_reset(self, clear_history::<E>);
+        self.world_mut()
+            .get_resource_or_insert_with(PredictionRegistry::default)
+            .timelines
+            .push(EventTimeline {
+                trim: timeline_trim::<E>,
+                frames: timeline_frames::<E>,
+                replay: timeline_replay::<E>,
+                acknowledged: timeline_acknowledged::<E>,
+                pending_secs: timeline_pending_secs::<E>,
+                reset: timeline_reset::<E>,
+                first_new: timeline_first_new::<E>,
+            });
+        self.insert_resource(ServerInputQueue::<E>::new(config))
+            .add_event::<E>()
+            .add_event::<FromClient<E>>()
+            .add_client_event::<PredictedInputBatch<E>>(channel)
+            .add_server_event::<InputAck<E>>(ChannelKind::Ordered)
+            .add_systems(
+                Update,
+                (
+                    (
+                        clear_history_on_ownership_change_system::<E>,
+                        receive_input_acks_system::<E>,
+                        expire_unacknowledged_inputs_system::<E>,
+                        record_predicted_inputs_system::<E>,
+                        coalesce_predicted_inputs_system::<E>
+                            .run_if(resource_exists::<InputCoalescing<E>>),
+                        send_predicted_inputs_system::<E>,
+                    )
+                        .chain()
+                        .in_set(PredictionSet::RecordInput),
+                    (
+                        queue_predicted_inputs_system::<E>,
+                        drain_predicted_inputs_system::<E>,
+                        send_input_acks_system::<E>,
+                    )
+                        .chain()
+                        .in_set(PredictionSet::ApplyServer)
+                        .run_if(server_or_singleplayer),
+                ),
+            )
+    }
+
+    fn add_client_predicted_event_coalesced<E>(
+        &mut self,
+        channel: impl Into<RepliconChannel>,
+        merge: fn(&E, &E) -> E,
+    ) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone,
+    {
+        self.insert_resource(InputCoalescing::<E>(merge))
+            .add_client_predicted_event::<E>(channel)
+    }
+
+    fn add_client_predicted_event_reliable<E>(&mut self) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone,
+    {
+        self.add_client_predicted_event_with_config::<E>(
+            ChannelKind::Ordered,
+            PredictedEventConfig {
+                redundancy: 0,
+                ..Default::default()
+            },
+        )
+    }
+
+    fn add_client_predicted_event_unreliable_with_ack<E>(&mut self, ack_timeout: f32) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone,
+    {
+        self.add_client_predicted_event_with_config::<E>(
+            ChannelKind::Unreliable,
+            PredictedEventConfig {
+                ack_timeout: Some(ack_timeout),
+                ..Default::default()
+            },
+        )
     }
 
     fn predict_event_for_component<E, T, C>(&mut self) -> &mut Self
     where
         E: Event + Serialize + DeserializeOwned + Debug + Clone,
-        T: Component + Serialize + DeserializeOwned,
+        T: Component + Serialize + DeserializeOwned + Clone,
+        C: Component + Predict<E, T> + Clone,
+    {
+        if register_prediction_context::<T>(self) {
+            replicate_context::<T>(self);
+        }
+        add_prediction_systems::<E, T, C>(self)
+    }
+
+    fn predict_event_for_component_no_replicate<E, T, C>(&mut self) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone,
+        T: Component + Serialize + DeserializeOwned + Clone,
+        C: Component + Predict<E, T> + Clone,
+    {
+        if register_prediction_context::<T>(self) {
+            register_context_marker_fns::<T>(self);
+        }
+        add_prediction_systems::<E, T, C>(self)
+    }
+
+    fn predict_event_for_component_with_validation<E, T, C>(
+        &mut self,
+        validate: fn(&E, &C, &T) -> Option<E>,
+    ) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone,
+        T: Component + Serialize + DeserializeOwned + Clone,
+        C: Component + Predict<E, T> + Clone,
+    {
+        self.insert_resource(EventValidation::<E, T, C>(validate))
+            .predict_event_for_component::<E, T, C>()
+    }
+
+    fn predict_event_for_component_with_spawn<E, T, C>(&mut self) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone,
+        T: Component + Serialize + DeserializeOwned + Clone,
+        C: Component + PredictEventSpawn<E, T> + Clone,
+    {
+        if !self.world().contains_resource::<PredictedEventSpawns>() {
+            self.init_resource::<PredictedEventSpawns>();
+            register_session_reset(self, reset_event_spawns);
+        }
+        self.insert_resource(EventSpawner::<E, T, C>(spawn_event_entity::<E, T, C>))
+            .predict_event_for_component::<E, T, C>()
+    }
+
+    fn predict_event_for_component_simple<E, C>(&mut self) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone,
+        C: Component + PredictSimple<E> + Clone,
+    {
+        // Already required if another event predicts the same component.
+        let _ = self
+            .world_mut()
+            .try_register_required_components::<C, NoContext>();
+        register_prediction_context::<NoContext>(self);
+        add_prediction_systems::<E, NoContext, C>(self)
+    }
+
+    fn predict_event_for_component_with_query<E, Q, C>(&mut self) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone,
+        Q: ReadOnlyQueryData + 'static,
+        C: Component + PredictQuery<E, Q> + Clone,
+    {
+        assert_predicted_event_registered::<E>(self);
+        register_predicted_component::<C>(self);
+        self.world_mut()
+            .resource_mut::<PredictedComponents<E>>()
+            .replays
+            .push(replay_event_query::<E, Q, C>);
+        self.add_systems(
+            Update,
+            server_query_update_system::<E, Q, C>
+                .in_set(PredictionSet::ApplyServer)
+                .after(drain_predicted_inputs_system::<E>)
+                .run_if(server_or_singleplayer), // Runs only on the server or a single player.
+        )
+    }
+
+    fn add_server_corrected_event<E>(&mut self, channel: impl Into<RepliconChannel>) -> &mut Self
+    where
+        E: ServerCorrection + Serialize + DeserializeOwned + Clone,
+    {
+        self.add_mapped_server_event::<E>(channel).add_systems(
+            PreUpdate,
+            server_correction_system::<E>
+                .in_set(InterpolationSet::Init)
+                .run_if(client_connected),
+        )
+    }
+
+    fn predict_event_for_components<E, T, C1, C2>(&mut self) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone,
+        T: Component + Serialize + DeserializeOwned + Clone,
+        C1: Component + PredictMulti<E, C2, T> + Clone,
+        C2: Component + Interpolate + Clone,
+    {
+        assert_predicted_event_registered::<E>(self);
+        if register_prediction_context::<T>(self) {
+            replicate_context::<T>(self);
+        }
+        register_predicted_component::<C1>(self);
+        register_predicted_component::<C2>(self);
+        self.world_mut()
+            .resource_mut::<PredictedComponents<E>>()
+            .replays
+            .push(replay_event_multi::<E, T, C1, C2>);
+        self.add_systems(
+            Update,
+            server_update_multi_system::<E, T, C1, C2>
+                .in_set(PredictionSet::ApplyServer)
+                .after(drain_predicted_inputs_system::<E>)
+                .run_if(server_or_singleplayer), // Runs only on the server or a single player.
+        )
+    }
+
+    fn set_correction_tolerance<C>(&mut self, tolerance: f32) -> &mut Self
+    where
+        C: Component + Interpolate,
+    {
+        self.insert_resource(CorrectionTolerance::<C> {
+            tolerance,
+            marker: PhantomData,
+        })
+    }
+
+    fn predict_component_from_state<C, F>(&mut self, f: F) -> &mut Self
+    where
+        C: Component + Interpolate + Clone,
+        F: Fn(&C, f32) -> C + Send + Sync + 'static,
+    {
+        register_predicted_component::<C>(self);
+        register_session_reset(self, remove_from_all::<StatePredictionSteps<C>>);
+        self.insert_resource(StatePrediction::<C>(Box::new(f)))
+            .add_systems(
+                Update,
+                (
+                    predicted_state_system::<C>
+                        .in_set(PredictionSet::Reconcile)
+                        .after(predicted_replay_system)
+                        .run_if(client_connected), // Runs only on clients.
+                    server_state_prediction_system::<C>
+                        .in_set(PredictionSet::ApplyServer)
+                        .run_if(server_or_singleplayer), // Runs only on the server or a single player.
+                ),
+            )
+    }
+
+    fn predict_remote_entities<E, T, C>(&mut self) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone,
+        T: Component + Clone,
         C: Component + Predict<E, T> + Clone,
     {
+        assert_predicted_event_registered::<E>(self);
+        let newly_registered = self
+            .world_mut()
+            .get_resource_or_insert_with(PredictionRegistry::default)
+            .remote_inputs
+            .insert(TypeId::of::<E>());
+        if newly_registered {
+            self.replicate::<RemoteInput<E>>().add_systems(
+                Update,
+                server_remote_input_system::<E>
+                    .in_set(PredictionSet::ApplyServer)
+                    .after(drain_predicted_inputs_system::<E>)
+                    .run_if(server_or_singleplayer), // Runs only on the server or a single player.
+            );
+        }
+        register_session_reset(self, remove_from_all::<RemotePredicted<C>>);
         self.add_systems(
             Update,
-            (
-                server_update_system::<E, T, C>.run_if(server_or_singleplayer), // Runs only on the server or a single player.
-                predicted_update_system::<E, T, C>.run_if(client_connected), // Runs only on clients.
-            ),
+            remote_prediction_system::<E, T, C>
+                .in_set(PredictionSet::Reconcile)
+                .run_if(client_connected), // Runs only on clients.
         )
-        .replicate::<T>()
     }
+
+    fn predict_event_for_resource<E, R>(&mut self) -> &mut Self
+    where
+        E: Event + Serialize + DeserializeOwned + Debug + Clone,
+        R: Resource + PredictResource<E> + Clone,
+    {
+        assert_predicted_event_registered::<E>(self);
+        register_session_reset(self, reset_resource_snapshot::<R>);
+        self.init_resource::<ResourceSnapshotBuffer<R>>()
+            .add_systems(
+                PreUpdate,
+                resource_snapshot_system::<R>
+                    .in_set(InterpolationSet::Init)
+                    .run_if(client_connected),
+            )
+            .add_systems(
+                Update,
+                (
+                    predicted_resource_update_system::<E, R>
+                        .in_set(PredictionSet::Reconcile)
+                        .run_if(client_connected), // Runs only on clients.
+                    server_resource_update_system::<E, R>
+                        .in_set(PredictionSet::ApplyServer)
+                        .after(drain_predicted_inputs_system::<E>)
+                        .run_if(server_or_singleplayer), // Runs only on the server or a single player.
+                ),
+            )
+    }
+}
+
+/// Replicates a prediction context and records its server state in [`ContextSnapshot`].
+fn replicate_context<T>(app: &mut App) -> &mut App
+where
+    T: Component + Serialize + DeserializeOwned + Clone,
+{
+    app.replicate::<T>();
+    register_context_marker_fns::<T>(app)
+}
+
+/// Writes received contexts into [`ContextSnapshot`] for entities with snapshots.
+fn register_context_marker_fns<T>(app: &mut App) -> &mut App
+where
+    T: Component + Serialize + DeserializeOwned + Clone,
+{
+    register_record_snapshots_marker(app);
+    app.set_marker_fns::<RecordSnapshotsMarker, T>(
+        write_context_component::<T>,
+        remove_context_component::<T>,
+    )
+}
+
+fn add_prediction_systems<E, T, C>(app: &mut App) -> &mut App
+where
+    E: Event + Serialize + DeserializeOwned + Debug + Clone,
+    T: Component + Clone,
+    C: Component + Predict<E, T> + Clone,
+{
+    assert_predicted_event_registered::<E>(app);
+    register_predicted_component::<C>(app);
+    app.world_mut().get_resource_or_insert_with(|| {
+        CorrectionMagnitude::<C>(<C as Predict<E, T>>::correction_magnitude)
+    });
+    app.world_mut()
+        .resource_mut::<PredictedComponents<E>>()
+        .replays
+        .push(replay_event::<E, T, C>);
+    app.add_systems(
+        Update,
+        server_update_system::<E, T, C>
+            .in_set(PredictionSet::ApplyServer)
+            .after(drain_predicted_inputs_system::<E>)
+            .run_if(server_or_singleplayer), // Runs only on the server or a single player.
+    )
+}
+
+/// Adds the rollback and correction systems of a predicted component once,
+/// no matter how many events predict it.
+fn register_predicted_component<C>(app: &mut App)
+where
+    C: Component + Interpolate + Clone,
+{
+    let newly_registered = app
+        .world_mut()
+        .get_resource_or_insert_with(PredictionRegistry::default)
+        .components
+        .insert(TypeId::of::<C>());
+    if !newly_registered {
+        return;
+    }
+
+    register_session_reset(
+        app,
+        remove_from_all::<(
+            PredictionState<C>,
+            PredictionError<C>,
+            TransitionBlend<C>,
+            DivergenceTrace<C>,
+        )>,
+    );
+    let mut registry = app.world_mut().resource_mut::<PredictionRegistry>();
+    registry.divergence_checks.push(DivergenceCheck {
+        begin: divergence_begin::<C>,
+        end: divergence_end::<C>,
+    });
+    registry.divergence_backups.push(DivergenceBackup {
+        save: divergence_save::<C>,
+        restore: divergence_restore::<C>,
+    });
+    app.add_event::<PredictionCorrected<C>>()
+        .add_event::<PredictionDivergence<C>>()
+        .add_systems(
+            Update,
+            (
+                transition_blend_init_system::<C>.in_set(PredictionSet::RecordInput),
+                (
+                    transition_restore_system::<C>,
+                    rollback_component_system::<C>,
+                )
+                    .chain()
+                    .in_set(PredictionSet::Rollback),
+                (
+                    prediction_correction_system::<C>,
+                    transition_blend_system::<C>,
+                )
+                    .chain()
+                    .in_set(PredictionSet::PostReconcile),
+            )
+                .run_if(client_connected), // Runs only on clients.
+        );
+}
+
+/// Adds the rollback system of a prediction context once,
+/// returns `false` if it was already registered.
+fn register_prediction_context<T>(app: &mut App) -> bool
+where
+    T: Component + Clone,
+{
+    let newly_registered = app
+        .world_mut()
+        .get_resource_or_insert_with(PredictionRegistry::default)
+        .contexts
+        .insert(TypeId::of::<T>());
+    if newly_registered {
+        register_session_reset(app, remove_from_all::<ContextSnapshot<T>>);
+        app.world_mut()
+            .resource_mut::<PredictionRegistry>()
+            .divergence_backups
+            .push(DivergenceBackup {
+                save: divergence_save::<T>,
+                restore: divergence_restore::<T>,
+            });
+        app.add_systems(
+            Update,
+            rollback_context_system::<T>
+                .in_set(PredictionSet::Rollback)
+                .run_if(client_connected), // Runs only on clients.
+        );
+    }
+    newly_registered
 }
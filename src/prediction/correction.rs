@@ -0,0 +1,287 @@
+use bevy::{
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{Event, EventWriter},
+        query::{Added, With, Without},
+        system::{Commands, Query, Res, Resource},
+    },
+    time::Time,
+    utils::HashSet,
+};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use crate::{
+    interpolation::{Interpolate, SnapshotBuffer},
+    Interpolated,
+};
+
+use super::{Predicted, PredictionPaused, PredictionState, Reconciled};
+
+/// Corrections with a smaller [`Interpolate::distance`] are treated as a perfect prediction.
+pub const CORRECTION_EPSILON: f32 = 1e-4;
+
+/// Sent on the client whenever the server corrected a mispredicted component.
+///
+/// Only sent for corrections above the [`CorrectionTolerance`], read it after
+/// [`PredictionSet::PostReconcile`](crate::PredictionSet::PostReconcile) to fix up state attached
+/// to the entity, e.g. particles or physics bodies.
+#[derive(Event)]
+pub struct PredictionCorrected<C: Component> {
+    pub entity: Entity,
+    /// Tick of the server snapshot that caused the correction.
+    pub tick: u32,
+    /// [`Interpolate::distance`] between the predicted and the corrected value.
+    pub error: f32,
+    /// Value before the correction.
+    pub predicted: C,
+    /// Value after the correction, now held by `C`.
+    pub corrected: C,
+}
+
+/// Presentation settings for mispredictions.
+///
+/// Insert this resource to blend corrections out over time instead of snapping the
+/// predicted component to the corrected value in a single frame.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct PredictionSmoothing {
+    /// Time in seconds over which a correction is blended out.
+    pub duration: f32,
+    /// Corrections with a larger [`Interpolate::distance`] snap immediately, e.g. teleports.
+    pub snap_threshold: f32,
+}
+
+impl Default for PredictionSmoothing {
+    fn default() -> Self {
+        Self {
+            duration: 0.1,
+            snap_threshold: 10.0,
+        }
+    }
+}
+
+/// Limits how many entities per predicted component type are corrected each frame.
+///
+/// Entities over the budget keep their uncorrected prediction until a later frame, ordered by
+/// [`Predict::correction_magnitude`](super::Predict::correction_magnitude).
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct PredictionCorrectionBudget {
+    pub max_corrections_per_frame: usize,
+}
+
+/// [`Predict::correction_magnitude`](super::Predict::correction_magnitude) of `C`, taken from its
+/// first registration.
+#[derive(Resource)]
+pub struct CorrectionMagnitude<C>(pub(super) fn(&C, &C) -> f32);
+
+/// Corrections of `C` up to this [`Interpolate::distance`] keep the client's current value,
+/// registered with
+/// [`AppPredictionExt::set_correction_tolerance`](super::AppPredictionExt::set_correction_tolerance).
+///
+/// Without it every correction is applied, but only those above [`CORRECTION_EPSILON`] are
+/// reported.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct CorrectionTolerance<C> {
+    pub tolerance: f32,
+    pub(super) marker: PhantomData<C>,
+}
+
+/// Insert this resource to blend predicted components from their last interpolated value when
+/// an entity becomes [`Predicted`], instead of jumping to the predicted value.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct PredictionTransition {
+    /// Number of frames the blend lasts.
+    pub frames: u32,
+}
+
+/// Blend of `C` from its value before the entity became [`Predicted`].
+///
+/// While it exists `C` holds the blended value after
+/// [`PredictionSet::PostReconcile`](crate::PredictionSet::PostReconcile), the predicted value is
+/// restored before the next rollback.
+#[derive(Component)]
+pub struct TransitionBlend<C: Component> {
+    /// Value before the entity became predicted.
+    pub from: C,
+    pub frames_remaining: u32,
+    /// Predicted value the blend ended at in the last frame.
+    pub predicted: Option<C>,
+}
+
+/// Visual error left over after a misprediction of `C`.
+///
+/// Only exists while [`PredictionSmoothing`] is blending out a correction. `C` always holds the
+/// corrected value, render systems should draw `presented` instead while this component exists.
+#[derive(Component)]
+pub struct PredictionError<C: Component> {
+    /// Value the entity would have had without the correction, advanced by new inputs.
+    pub from: C,
+    /// Blend between `from` and the corrected value.
+    pub presented: C,
+    /// Time in seconds since the correction happened.
+    pub elapsed: f32,
+}
+
+/// Starts a [`TransitionBlend`] for entities that just became predicted.
+pub fn transition_blend_init_system<C: Component + Clone>(
+    q_new: Query<(Entity, &C), Added<Predicted>>,
+    transition: Option<Res<PredictionTransition>>,
+    mut commands: Commands,
+) {
+    let Some(transition) = transition else {
+        return;
+    };
+    if transition.frames == 0 {
+        return;
+    }
+    for (entity, component) in &q_new {
+        commands.entity(entity).insert(TransitionBlend {
+            from: component.clone(),
+            frames_remaining: transition.frames,
+            predicted: None,
+        });
+    }
+}
+
+/// Restores the predicted value of components that were blended in the last frame.
+pub fn transition_restore_system<C: Component + Clone>(
+    mut q_blending: Query<(&mut C, &TransitionBlend<C>)>,
+) {
+    for (mut component, blend) in &mut q_blending {
+        if let Some(predicted) = &blend.predicted {
+            *component = predicted.clone();
+        }
+    }
+}
+
+/// Blends `C` from its value before the entity became predicted to the predicted value.
+pub fn transition_blend_system<C: Component + Interpolate + Clone>(
+    mut q_blending: Query<(Entity, &mut C, &mut TransitionBlend<C>)>,
+    transition: Option<Res<PredictionTransition>>,
+    mut commands: Commands,
+) {
+    let frames = transition.map_or(1, |transition| transition.frames.max(1));
+    for (entity, mut component, mut blend) in &mut q_blending {
+        if blend.frames_remaining == 0 {
+            commands.entity(entity).remove::<TransitionBlend<C>>();
+            continue;
+        }
+        let t = 1.0 - blend.frames_remaining as f32 / (frames + 1) as f32;
+        blend.predicted = Some(component.clone());
+        *component = blend.from.interpolate(component.clone(), t);
+        blend.frames_remaining -= 1;
+    }
+}
+
+/// Compares the reconciled value of `C` with the uncorrected prediction, sends
+/// [`PredictionCorrected`] and blends out corrections according to [`PredictionSmoothing`].
+pub fn prediction_correction_system<C: Component + Interpolate + Clone>(
+    mut q_predicted: Query<
+        (
+            Entity,
+            &mut C,
+            &SnapshotBuffer<C>,
+            &PredictionState<C>,
+            Option<&mut PredictionError<C>>,
+        ),
+        (
+            With<Predicted>,
+            Without<Interpolated>,
+            Without<PredictionPaused>,
+        ),
+    >,
+    mut corrections: EventWriter<PredictionCorrected<C>>,
+    smoothing: Option<Res<PredictionSmoothing>>,
+    budget: Option<Res<PredictionCorrectionBudget>>,
+    magnitude: Option<Res<CorrectionMagnitude<C>>>,
+    tolerance: Option<Res<CorrectionTolerance<C>>>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    // Without an explicit tolerance small corrections are still applied, only reporting is skipped
+    let keep_predicted = tolerance.is_some();
+    let tolerance = tolerance.map_or(CORRECTION_EPSILON, |tolerance| tolerance.tolerance);
+    let mut deferred = HashSet::new();
+    if let Some(budget) = budget.as_deref() {
+        let mut candidates: Vec<(Entity, f32)> = q_predicted
+            .iter()
+            .filter(|(_, component, _, state, _)| state.uncorrected.distance(component) > tolerance)
+            .map(|(entity, component, _, state, _)| {
+                let magnitude = magnitude.as_deref().map_or(f32::INFINITY, |magnitude| {
+                    (magnitude.0)(&state.uncorrected, component)
+                });
+                (entity, magnitude)
+            })
+            .collect();
+        if candidates.len() > budget.max_corrections_per_frame {
+            candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+            deferred.extend(
+                candidates
+                    .drain(budget.max_corrections_per_frame..)
+                    .map(|(entity, _)| entity),
+            );
+        }
+    }
+
+    for (entity, mut component, snapshot_buffer, state, error) in &mut q_predicted {
+        if deferred.contains(&entity) {
+            *component = state.uncorrected.clone();
+            // Rolls back again in the next frame
+            commands.entity(entity).remove::<Reconciled>();
+            continue;
+        }
+
+        let correction = state.uncorrected.distance(&component);
+        if correction <= tolerance {
+            if keep_predicted {
+                // Effectively a perfect prediction, don't replace it with floating point drift
+                *component = state.uncorrected.clone();
+            }
+        } else {
+            corrections.send(PredictionCorrected {
+                entity,
+                tick: snapshot_buffer.latest_snapshot_tick(),
+                error: correction,
+                predicted: state.uncorrected.clone(),
+                corrected: component.clone(),
+            });
+        }
+
+        let Some(smoothing) = smoothing.as_deref() else {
+            continue;
+        };
+        match error {
+            Some(_) if correction > smoothing.snap_threshold => {
+                commands.entity(entity).remove::<PredictionError<C>>();
+            }
+            Some(mut error) => {
+                if correction > tolerance {
+                    // Restart the blend from what is currently presented
+                    error.from = error.from.interpolate(
+                        state.uncorrected.clone(),
+                        error.elapsed / smoothing.duration,
+                    );
+                    error.elapsed = 0.0;
+                } else {
+                    error.elapsed += time.delta_secs();
+                }
+                if error.elapsed >= smoothing.duration {
+                    commands.entity(entity).remove::<PredictionError<C>>();
+                } else {
+                    error.presented = error
+                        .from
+                        .interpolate(component.clone(), error.elapsed / smoothing.duration);
+                }
+            }
+            None if correction > tolerance && correction <= smoothing.snap_threshold => {
+                commands.entity(entity).insert(PredictionError {
+                    from: state.uncorrected.clone(),
+                    presented: state.uncorrected.clone(),
+                    elapsed: 0.0,
+                });
+            }
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,500 @@
+use bevy::{
+    ecs::{event::Event, system::Resource},
+    log::warn,
+};
+use bevy_replicon::core::replicon_tick::RepliconTick;
+use serde::{Deserialize, Serialize};
+use std::any::type_name;
+use std::collections::vec_deque::Iter;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+
+#[derive(Serialize, Debug)]
+pub struct EventSnapshot<T: Event> {
+    pub value: T,
+    pub tick: RepliconTick,
+    /// [`FrameCount`](bevy::core::FrameCount) at recording, orders replays across event types.
+    pub frame: u32,
+    pub delta_time: f32,
+    /// Input sequence, identical to [`PredictedInput::sequence`](super::PredictedInput::sequence).
+    pub sequence: u32,
+    /// Seconds since the history was created, used for [`PredictedEventConfig::ack_timeout`].
+    pub recorded_at: f32,
+}
+
+/// Per event type settings for client predicted events.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct PredictedEventConfig {
+    /// Maximum number of events kept in the history, the oldest are dropped first.
+    pub max_entries: Option<usize>,
+    /// Maximum age of events kept in the history in seconds, summed from recorded frame deltas.
+    pub max_age: Option<f32>,
+    /// Maximum number of inputs per client the server applies each tick.
+    pub input_budget: usize,
+    /// Maximum number of inputs per client the server queues.
+    pub input_queue_capacity: usize,
+    /// Number of previously sent inputs repeated in every message, useful on unreliable channels.
+    pub redundancy: usize,
+    /// Seconds after which inputs the server has not acknowledged with
+    /// [`InputAck`](super::InputAck) are considered lost and removed from the history.
+    pub ack_timeout: Option<f32>,
+    /// Inputs are stamped this many ticks ahead of the latest confirmed tick and only applied,
+    /// on the client and the server, once that tick arrived. `0` predicts immediately.
+    pub input_delay_ticks: u32,
+    /// Inputs are recorded with the tick duration instead of the frame delta, so live
+    /// predictions, replays and the server apply identical deltas on any hardware.
+    ///
+    /// Requires sending at most one event per tick, e.g. from a `FixedUpdate` running at the
+    /// tick rate, otherwise held inputs are applied once per frame with a whole tick each.
+    pub fixed_delta_time: bool,
+}
+
+impl Default for PredictedEventConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: Some(512),
+            max_age: Some(2.0),
+            input_budget: 32,
+            input_queue_capacity: 128,
+            redundancy: 0,
+            ack_timeout: None,
+            input_delay_ticks: 0,
+            fixed_delta_time: false,
+        }
+    }
+}
+
+/// Whether `sequence` was sent after `other`, comparing wrapping-aware.
+pub(super) fn sequence_is_newer(sequence: u32, other: u32) -> bool {
+    (sequence.wrapping_sub(other) as i32) > 0
+}
+
+/// Seconds between two warnings about a full event history.
+pub(super) const HISTORY_LIMIT_WARNING_INTERVAL: f32 = 1.0;
+
+/// Serializable with any serde format when `T: Serialize`, e.g. to attach it to bug reports.
+#[derive(Resource, Serialize)]
+pub struct PredictedEventHistory<T: Event> {
+    /// Recorded events, oldest first. Private to keep the bookkeeping of the current frame,
+    /// coalescing and acknowledgements consistent.
+    pub(super) events: VecDeque<EventSnapshot<T>>,
+    pub config: PredictedEventConfig,
+    #[serde(skip)]
+    warning_cooldown: f32,
+    /// Number of events at the end of `events` recorded during the current frame.
+    #[serde(skip)]
+    pub(super) recent: usize,
+    #[serde(skip)]
+    next_sequence: u32,
+    /// Seconds since the history was created.
+    #[serde(skip)]
+    elapsed: f32,
+    /// Latest tick delayed inputs are due at, see [`PredictedEventConfig::input_delay_ticks`].
+    #[serde(skip)]
+    pub(super) due_tick: Option<RepliconTick>,
+    /// Number of delayed inputs that became due during the current frame.
+    #[serde(skip)]
+    pub(super) newly_due: usize,
+    /// Number of events sent to the server during the current frame.
+    #[serde(skip)]
+    pub(super) to_send: usize,
+    /// Number of events at the end of `events` in the open coalescing window, not sent yet.
+    #[serde(skip)]
+    pub(super) open: usize,
+    /// History clock at which the open coalescing window started.
+    #[serde(skip)]
+    window_start: f32,
+    /// Sequence of the latest input the server acknowledged.
+    pub acknowledged: Option<u32>,
+}
+
+impl<T: Event> Default for PredictedEventHistory<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Event> PredictedEventHistory<T> {
+    pub fn new() -> PredictedEventHistory<T> {
+        Self::with_config(PredictedEventConfig::default())
+    }
+
+    pub fn with_config(config: PredictedEventConfig) -> PredictedEventHistory<T> {
+        Self {
+            events: VecDeque::new(),
+            config,
+            warning_cooldown: 0.0,
+            recent: 0,
+            next_sequence: 0,
+            elapsed: 0.0,
+            due_tick: None,
+            newly_due: 0,
+            to_send: 0,
+            open: 0,
+            window_start: 0.0,
+            acknowledged: None,
+        }
+    }
+
+    /// Records an event, dropping the oldest events if the configured limits are exceeded.
+    pub fn insert(
+        &mut self,
+        value: T,
+        tick: RepliconTick,
+        frame: u32,
+        delta_time: f32,
+    ) -> &mut Self {
+        self.events.push_back(EventSnapshot {
+            value,
+            tick,
+            frame,
+            delta_time,
+            sequence: self.next_sequence,
+            recorded_at: self.elapsed,
+        });
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+        self.warning_cooldown -= delta_time;
+
+        let mut dropped = 0;
+        if let Some(max_entries) = self.config.max_entries {
+            while self.events.len() > max_entries {
+                self.events.pop_front();
+                dropped += 1;
+            }
+        }
+        if let Some(max_age) = self.config.max_age {
+            let mut age: f32 = self.events.iter().map(|e| e.delta_time).sum();
+            while age > max_age && self.events.len() > 1 {
+                if let Some(oldest) = self.events.pop_front() {
+                    age -= oldest.delta_time;
+                    dropped += 1;
+                }
+            }
+        }
+
+        if dropped > 0 && self.warning_cooldown <= 0.0 {
+            warn!(
+                "predicted event history for {} is full, dropped {dropped} oldest events",
+                type_name::<T>()
+            );
+            self.warning_cooldown = HISTORY_LIMIT_WARNING_INTERVAL;
+        }
+        self
+    }
+
+    /// Drops all events already reflected in the server state.
+    ///
+    /// Once the server acknowledged an input with [`InputAck`](super::InputAck), all inputs up to
+    /// it are dropped. Before that, events recorded before `latest_server_snapshot_tick` are
+    /// dropped instead, comparing ticks wrapping-aware. Events recorded at exactly
+    /// `latest_server_snapshot_tick` are kept and replayed, the server applies them after the
+    /// snapshot of that tick was taken.
+    pub fn remove_stale(&mut self, latest_server_snapshot_tick: RepliconTick) -> &mut Self {
+        match self.acknowledged {
+            Some(acknowledged) => {
+                while self
+                    .events
+                    .front()
+                    .is_some_and(|oldest| !sequence_is_newer(oldest.sequence, acknowledged))
+                {
+                    self.events.pop_front();
+                }
+            }
+            None => {
+                if let Some(last_index) = self
+                    .events
+                    .iter()
+                    .position(|v| v.tick >= latest_server_snapshot_tick)
+                {
+                    self.events.drain(0..last_index);
+                } else {
+                    self.events.clear();
+                }
+            }
+        }
+        self
+    }
+
+    /// Advances the history clock and drops inputs not acknowledged within
+    /// [`PredictedEventConfig::ack_timeout`].
+    ///
+    /// Returns the number of dropped inputs.
+    pub fn expire_unacknowledged(&mut self, delta_time: f32) -> usize {
+        self.elapsed += delta_time;
+        let Some(ack_timeout) = self.config.ack_timeout else {
+            return 0;
+        };
+
+        let mut expired = 0;
+        while self.events.front().is_some_and(|oldest| {
+            self.elapsed - oldest.recorded_at > ack_timeout
+                && self
+                    .acknowledged
+                    .is_none_or(|acknowledged| sequence_is_newer(oldest.sequence, acknowledged))
+        }) {
+            self.events.pop_front();
+            expired += 1;
+        }
+        expired
+    }
+
+    /// Number of events at the start of `events` that are applied, all events unless inputs
+    /// are delayed.
+    pub fn due_len(&self) -> usize {
+        if self.config.input_delay_ticks == 0 {
+            return self.events.len();
+        }
+        let Some(due_tick) = self.due_tick else {
+            return 0;
+        };
+        self.events
+            .iter()
+            .take_while(|event_snapshot| event_snapshot.tick <= due_tick)
+            .count()
+    }
+
+    /// Due events not reflected in the server state at `tick`, with their index, see
+    /// [`Self::remove_stale`].
+    pub(super) fn unconfirmed(
+        &self,
+        tick: RepliconTick,
+    ) -> impl Iterator<Item = (usize, &EventSnapshot<T>)> {
+        // Delayed inputs that are not due yet are not applied
+        self.events
+            .iter()
+            .take(self.due_len())
+            .enumerate()
+            .filter(move |(_, event_snapshot)| {
+                self.acknowledged.is_some() || event_snapshot.tick >= tick
+            })
+    }
+
+    /// Index of the first event applied for the first time during the current frame.
+    pub(super) fn first_new(&self) -> usize {
+        if self.config.input_delay_ticks == 0 {
+            self.events.len().saturating_sub(self.recent)
+        } else {
+            self.due_len().saturating_sub(self.newly_due)
+        }
+    }
+
+    /// Removes all events, e.g. after the predicted entity changed.
+    pub fn clear(&mut self) {
+        self.events.clear();
+        self.recent = 0;
+        self.to_send = 0;
+        self.open = 0;
+    }
+
+    /// Merges the events of the last coalescing window into a single event once the window
+    /// lasted `window_secs`, events recorded during the current frame start the next window.
+    ///
+    /// The merged event keeps the sequence and tick of its last event and the summed delta time.
+    pub fn coalesce(&mut self, merge: fn(&T, &T) -> T, window_secs: f32) {
+        let closing = self.open.min(self.events.len().saturating_sub(self.recent));
+        if self.elapsed - self.window_start < window_secs {
+            self.open = closing + self.recent;
+            self.to_send = 0;
+            return;
+        }
+
+        self.window_start = self.elapsed;
+        self.open = self.recent;
+        self.to_send = 0;
+        let end = self.events.len() - self.recent;
+        let mut window = self.events.drain(end - closing..end);
+        let Some(mut merged) = window.next() else {
+            return;
+        };
+        for event_snapshot in window {
+            merged.value = merge(&merged.value, &event_snapshot.value);
+            merged.delta_time += event_snapshot.delta_time;
+            merged.tick = event_snapshot.tick;
+            merged.sequence = event_snapshot.sequence;
+        }
+        self.events.insert(end - closing, merged);
+        self.to_send = 1;
+    }
+
+    /// Number of recorded events.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Tick of the oldest recorded event.
+    pub fn oldest_tick(&self) -> Option<RepliconTick> {
+        self.events
+            .front()
+            .map(|event_snapshot| event_snapshot.tick)
+    }
+
+    /// Tick of the newest recorded event.
+    pub fn newest_tick(&self) -> Option<RepliconTick> {
+        self.events.back().map(|event_snapshot| event_snapshot.tick)
+    }
+
+    /// Iterates all recorded events, oldest first, without dropping stale ones.
+    pub fn iter(&self) -> Iter<'_, EventSnapshot<T>> {
+        self.events.iter()
+    }
+
+    /// Iterates the events recorded after `since_tick` that the server did not acknowledge yet,
+    /// oldest first, e.g. for an action queue display. Ticks are compared wrapping-aware.
+    pub fn peek_unconfirmed(&self, since_tick: u32) -> impl Iterator<Item = &EventSnapshot<T>> {
+        let since_tick = RepliconTick::new(since_tick);
+        self.events.iter().filter(move |event_snapshot| {
+            event_snapshot.tick > since_tick
+                && self.acknowledged.is_none_or(|acknowledged| {
+                    sequence_is_newer(event_snapshot.sequence, acknowledged)
+                })
+        })
+    }
+
+    /// Marks all inputs up to `sequence` as applied by the server.
+    pub fn acknowledge(&mut self, sequence: u32) {
+        if self
+            .acknowledged
+            .is_none_or(|acknowledged| sequence_is_newer(sequence, acknowledged))
+        {
+            self.acknowledged = Some(sequence);
+        }
+    }
+
+    /// Drops stale events with [`Self::remove_stale`] and iterates the remaining ones,
+    /// oldest first.
+    ///
+    /// Mutates the history, use [`Self::iter`] for a read-only view.
+    pub fn predict(
+        &mut self,
+        latest_server_snapshot_tick: RepliconTick,
+    ) -> Iter<'_, EventSnapshot<T>> {
+        self.remove_stale(latest_server_snapshot_tick);
+        self.events.iter()
+    }
+}
+
+/// Formats the history of `E` as one line per event, oldest first, for logs and bug reports.
+pub fn dump_prediction_history<E: Event + Debug>(history: &PredictedEventHistory<E>) -> String {
+    history
+        .iter()
+        .map(|snapshot| {
+            format!(
+                "tick {} delta {:.4}: {:?}\n",
+                snapshot.tick.get(),
+                snapshot.delta_time,
+                snapshot.value
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Event, Clone, Debug, PartialEq)]
+    struct Move(f32);
+
+    fn ticks(history: &PredictedEventHistory<Move>) -> Vec<u32> {
+        history.iter().map(|e| e.tick.get()).collect()
+    }
+
+    fn history_at(ticks: &[u32]) -> PredictedEventHistory<Move> {
+        let mut history = PredictedEventHistory::new();
+        for (frame, &tick) in ticks.iter().enumerate() {
+            history.insert(Move(1.0), RepliconTick::new(tick), frame as u32, 0.01);
+        }
+        history
+    }
+
+    #[test]
+    fn remove_stale_across_wrap() {
+        let mut history = history_at(&[u32::MAX - 1, u32::MAX, 0, 1]);
+        history.remove_stale(RepliconTick::new(0));
+        assert_eq!(ticks(&history), [0, 1]);
+    }
+
+    #[test]
+    fn remove_stale_before_wrap_keeps_wrapped_events() {
+        let mut history = history_at(&[u32::MAX - 1, u32::MAX, 0, 1]);
+        history.remove_stale(RepliconTick::new(u32::MAX));
+        assert_eq!(ticks(&history), [u32::MAX, 0, 1]);
+    }
+
+    #[test]
+    fn remove_stale_after_wrap_clears_confirmed_events() {
+        let mut history = history_at(&[u32::MAX - 1, u32::MAX, 0, 1]);
+        history.remove_stale(RepliconTick::new(2));
+        assert!(history.is_empty());
+    }
+
+    #[test]
+    fn events_at_server_tick_are_replayed() {
+        let mut history = history_at(&[4, 5, 5, 6]);
+        let replayed: Vec<u32> = history
+            .predict(RepliconTick::new(5))
+            .map(|e| e.tick.get())
+            .collect();
+        assert_eq!(replayed, [5, 5, 6]);
+
+        let unconfirmed: Vec<usize> = history
+            .unconfirmed(RepliconTick::new(5))
+            .map(|(index, _)| index)
+            .collect();
+        assert_eq!(unconfirmed, [0, 1, 2]);
+    }
+
+    #[test]
+    fn inspection_does_not_trim() {
+        let mut history = history_at(&[3, 4, 5]);
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.oldest_tick(), Some(RepliconTick::new(3)));
+        assert_eq!(history.newest_tick(), Some(RepliconTick::new(5)));
+        assert_eq!(history.peek_unconfirmed(3).count(), 2);
+        assert_eq!(ticks(&history), [3, 4, 5]);
+
+        // A snapshot older than every event confirms none of them
+        history.remove_stale(RepliconTick::new(2));
+        assert_eq!(ticks(&history), [3, 4, 5]);
+
+        history.clear();
+        assert!(history.is_empty());
+        assert_eq!(history.oldest_tick(), None);
+    }
+
+    #[test]
+    fn acknowledged_trim_keeps_unacknowledged_inputs() {
+        let mut history = history_at(&[3, 4, 5, 6]);
+        history.acknowledge(1);
+        // An older acknowledgement arriving late does not move the acknowledgement back
+        history.acknowledge(0);
+        assert_eq!(history.acknowledged, Some(1));
+
+        // Acknowledged inputs are trimmed by sequence, the snapshot tick is not used
+        history.remove_stale(RepliconTick::new(6));
+        assert_eq!(ticks(&history), [5, 6]);
+        assert_eq!(history.peek_unconfirmed(0).count(), 2);
+    }
+
+    #[test]
+    fn limits_only_drop_excess_events() {
+        let mut history = PredictedEventHistory::with_config(PredictedEventConfig {
+            max_entries: Some(3),
+            max_age: Some(0.25),
+            ..Default::default()
+        });
+        for tick in 0..3 {
+            history.insert(Move(1.0), RepliconTick::new(tick), tick, 0.1);
+        }
+        // 0.3 seconds exceed the maximum age, only the oldest event is dropped
+        assert_eq!(ticks(&history), [1, 2]);
+
+        history.insert(Move(1.0), RepliconTick::new(3), 3, 0.01);
+        history.insert(Move(1.0), RepliconTick::new(4), 4, 0.01);
+        assert_eq!(ticks(&history), [2, 3, 4]);
+    }
+}
@@ -0,0 +1,527 @@
+use bevy::{
+    core::FrameCount,
+    ecs::{
+        change_detection::DetectChanges,
+        event::{Event, EventReader, EventWriter},
+        system::{Res, ResMut, Resource},
+    },
+    log::{debug, warn},
+    time::Time,
+    utils::HashMap,
+};
+use bevy_replicon::{
+    core::{replicon_server::RepliconServer, replicon_tick::RepliconTick},
+    prelude::{ClientId, FromClient, SendMode, ServerEvent, ToClients},
+    server::server_tick::ServerTick,
+};
+use serde::{Deserialize, Serialize};
+use std::any::type_name;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use crate::interpolation::SnapshotInterpolationConfig;
+
+use super::{
+    sequence_is_newer, EstimatedServerTick, PredictInfo, PredictedEventConfig,
+    PredictedEventHistory, PredictionPaused,
+};
+
+/// A predicted event as sent to the server, stamped with the tick it was recorded at.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PredictedInput<E: Event> {
+    pub event: E,
+    pub tick: RepliconTick,
+    pub delta_time: f32,
+    /// Increases by one for every input of this event type a client sends.
+    pub sequence: u32,
+}
+
+impl<E: Event> PredictedInput<E> {
+    /// Server side application info, the delta time is clamped to `0.0..=max_delta_time`.
+    ///
+    /// The delta comes from the client, so negative or invalid values are never trusted.
+    pub fn info(&self, max_delta_time: f32) -> PredictInfo {
+        PredictInfo {
+            tick: self.tick,
+            // `f32::max` also replaces NaN
+            delta_time: self.delta_time.max(0.0).min(max_delta_time),
+            is_resimulation: false,
+            sequence: self.sequence,
+        }
+    }
+}
+
+/// Sent to a client with the sequence of its latest input of `E` the server applied.
+///
+/// Replicon delivers it together with the replication message of the same tick, so the client
+/// knows exactly which inputs the received state already reflects.
+#[derive(Event, Serialize, Deserialize, Clone, Debug)]
+pub struct InputAck<E> {
+    pub sequence: u32,
+    #[serde(skip)]
+    marker: PhantomData<E>,
+}
+
+impl<E> InputAck<E> {
+    pub fn new(sequence: u32) -> Self {
+        Self {
+            sequence,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// The latest inputs of a client, repeated according to [`PredictedEventConfig::redundancy`]
+/// so a single lost message does not lose inputs.
+#[derive(Event, Serialize, Deserialize, Clone, Debug)]
+pub struct PredictedInputBatch<E: Event> {
+    pub inputs: Vec<PredictedInput<E>>,
+}
+
+/// Server side queue of client inputs for `E`, ordered by tick per client.
+///
+/// Every server tick at most [`PredictedEventConfig::input_budget`] inputs per client are
+/// released, overflowing queues drop their oldest inputs.
+#[derive(Resource)]
+pub struct ServerInputQueue<E: Event> {
+    queues: HashMap<ClientId, VecDeque<PredictedInput<E>>>,
+    last_sequences: HashMap<ClientId, u32>,
+    ready: Vec<(ClientId, PredictedInput<E>)>,
+    dropped: HashMap<ClientId, u64>,
+    config: PredictedEventConfig,
+}
+
+impl<E: Event> ServerInputQueue<E> {
+    pub fn new(config: PredictedEventConfig) -> Self {
+        Self {
+            queues: HashMap::default(),
+            last_sequences: HashMap::default(),
+            ready: Vec::new(),
+            dropped: HashMap::default(),
+            config,
+        }
+    }
+
+    /// Queues an input behind all inputs of the same client with an equal or lower tick.
+    ///
+    /// Inputs with a sequence that was already received from this client are ignored.
+    pub fn push(&mut self, client_id: ClientId, input: PredictedInput<E>) {
+        if self
+            .last_sequences
+            .get(&client_id)
+            .is_some_and(|&last| !sequence_is_newer(input.sequence, last))
+        {
+            return;
+        }
+        self.last_sequences.insert(client_id, input.sequence);
+
+        let queue = self.queues.entry(client_id).or_default();
+        let index = queue
+            .iter()
+            .rposition(|queued| queued.tick <= input.tick)
+            .map_or(0, |index| index + 1);
+        queue.insert(index, input);
+
+        if queue.len() > self.config.input_queue_capacity {
+            let dropped = queue.len() - self.config.input_queue_capacity;
+            queue.drain(..dropped);
+            *self.dropped.entry(client_id).or_default() += dropped as u64;
+            warn!(
+                "input queue of {} for client {client_id:?} is full, dropped {dropped} oldest inputs",
+                type_name::<E>()
+            );
+        }
+    }
+
+    /// Forgets all inputs of a client, e.g. after it disconnected.
+    pub fn remove_client(&mut self, client_id: ClientId) {
+        self.queues.remove(&client_id);
+        self.last_sequences.remove(&client_id);
+        self.dropped.remove(&client_id);
+    }
+
+    /// Number of inputs of a client dropped because it sent more than the queue could hold,
+    /// e.g. to detect clients spamming inputs.
+    pub fn dropped_inputs(&self, client_id: ClientId) -> u64 {
+        self.dropped.get(&client_id).copied().unwrap_or_default()
+    }
+
+    /// Inputs released for the current server tick.
+    pub fn ready(&self) -> impl Iterator<Item = &(ClientId, PredictedInput<E>)> {
+        self.ready.iter()
+    }
+
+    /// Releases the inputs due at `tick`, delayed inputs are held back until their tick.
+    fn drain(&mut self, tick: Option<RepliconTick>) {
+        self.ready.clear();
+        for (client_id, queue) in self.queues.iter_mut() {
+            let count = match tick {
+                Some(tick) if self.config.input_delay_ticks > 0 => {
+                    queue.iter().take_while(|input| input.tick <= tick).count()
+                }
+                _ => queue.len(),
+            }
+            .min(self.config.input_budget);
+            self.ready
+                .extend(queue.drain(..count).map(|input| (*client_id, input)));
+        }
+    }
+}
+
+/// Merges two inputs of `E` into one, registered with
+/// [`AppPredictionExt::add_client_predicted_event_coalesced`](super::AppPredictionExt::add_client_predicted_event_coalesced).
+#[derive(Resource)]
+pub struct InputCoalescing<E>(pub fn(&E, &E) -> E);
+
+/// Sends the inputs recorded this frame to the server.
+///
+/// Each message repeats the last [`PredictedEventConfig::redundancy`] inputs.
+pub fn send_predicted_inputs_system<E: Event + Clone>(
+    history: Res<PredictedEventHistory<E>>,
+    mut batches: EventWriter<PredictedInputBatch<E>>,
+) {
+    if history.to_send == 0 {
+        return;
+    }
+
+    // Events in an open coalescing window are sent once it closes
+    let end = history.events.len() - history.open;
+    let window = history.to_send + history.config.redundancy;
+    let skipped = end.saturating_sub(window);
+    batches.send(PredictedInputBatch {
+        inputs: history
+            .events
+            .iter()
+            .take(end)
+            .skip(skipped)
+            .map(|snapshot| PredictedInput {
+                event: snapshot.value.clone(),
+                tick: snapshot.tick,
+                delta_time: snapshot.delta_time,
+                sequence: snapshot.sequence,
+            })
+            .collect(),
+    });
+}
+
+/// Queues received client inputs until the next server tick.
+pub fn queue_predicted_inputs_system<E: Event + Clone>(
+    mut received: EventReader<FromClient<PredictedInputBatch<E>>>,
+    mut server_events: EventReader<ServerEvent>,
+    mut queue: ResMut<ServerInputQueue<E>>,
+) {
+    for event in server_events.read() {
+        if let ServerEvent::ClientDisconnected { client_id, .. } = event {
+            queue.remove_client(*client_id);
+        }
+    }
+    for FromClient { client_id, event } in received.read() {
+        for input in &event.inputs {
+            queue.push(*client_id, input.clone());
+        }
+    }
+}
+
+/// Releases the inputs of each client for the current server tick.
+///
+/// Released inputs are also sent as [`FromClient<E>`] events for game systems.
+pub fn drain_predicted_inputs_system<E: Event + Clone>(
+    mut queue: ResMut<ServerInputQueue<E>>,
+    mut events: EventWriter<FromClient<E>>,
+    tick: Res<ServerTick>,
+    server: Res<RepliconServer>,
+) {
+    // Without a running server there are no ticks, release inputs every frame instead.
+    if !tick.is_changed() && server.is_running() {
+        queue.ready.clear();
+        return;
+    }
+
+    queue.drain(server.is_running().then_some(**tick));
+    for (client_id, input) in &queue.ready {
+        events.send(FromClient {
+            client_id: *client_id,
+            event: input.event.clone(),
+        });
+    }
+}
+
+/// Acknowledges the latest input of each client released this tick.
+pub fn send_input_acks_system<E: Event + Clone>(
+    queue: Res<ServerInputQueue<E>>,
+    mut acks: EventWriter<ToClients<InputAck<E>>>,
+) {
+    let mut latest: HashMap<ClientId, u32> = HashMap::default();
+    for (client_id, input) in queue.ready() {
+        latest
+            .entry(*client_id)
+            .and_modify(|sequence| {
+                if sequence_is_newer(input.sequence, *sequence) {
+                    *sequence = input.sequence;
+                }
+            })
+            .or_insert(input.sequence);
+    }
+    for (client_id, sequence) in latest {
+        acks.send(ToClients {
+            mode: SendMode::Direct(client_id),
+            event: InputAck::new(sequence),
+        });
+    }
+}
+
+/// Applies received [`InputAck`]s to the history of `E`.
+pub fn receive_input_acks_system<E: Event>(
+    mut acks: EventReader<InputAck<E>>,
+    mut history: ResMut<PredictedEventHistory<E>>,
+) {
+    for ack in acks.read() {
+        history.acknowledge(ack.sequence);
+    }
+}
+
+/// Drops inputs the server did not acknowledge in time, they were most likely lost in transit.
+pub fn expire_unacknowledged_inputs_system<E: Event>(
+    mut history: ResMut<PredictedEventHistory<E>>,
+    time: Res<Time>,
+) {
+    let expired = history.expire_unacknowledged(time.delta_secs());
+    if expired > 0 {
+        debug!(
+            "dropped {expired} unacknowledged inputs of {}",
+            type_name::<E>()
+        );
+    }
+}
+
+/// Records local predicted events into their history with the next input sequence.
+///
+/// Events are also stamped with the [`EstimatedServerTick`] for [`PredictInfo::tick`], but which
+/// of them are already reflected in the server state is decided by [`InputAck`].
+pub fn record_predicted_inputs_system<E: Event + Clone>(
+    mut local_events: EventReader<E>,
+    mut history: ResMut<PredictedEventHistory<E>>,
+    estimated_tick: Res<EstimatedServerTick>,
+    frame: Res<FrameCount>,
+    time: Res<Time>,
+    config: Res<SnapshotInterpolationConfig>,
+    paused: Option<Res<PredictionPaused>>,
+) {
+    history.recent = 0;
+    if paused.is_some() {
+        history.to_send = 0;
+        local_events.clear();
+        return;
+    }
+    let tick = estimated_tick.get();
+    let input_delay_ticks = history.config.input_delay_ticks;
+    if input_delay_ticks > 0 {
+        let previous_due_tick = history.due_tick;
+        history.newly_due = history
+            .events
+            .iter()
+            .filter(|event_snapshot| {
+                event_snapshot.tick <= tick
+                    && previous_due_tick.is_none_or(|previous| event_snapshot.tick > previous)
+            })
+            .count();
+        history.due_tick = Some(tick);
+    }
+
+    let input_tick = RepliconTick::new(tick.get().wrapping_add(input_delay_ticks));
+    let delta_time = if history.config.fixed_delta_time {
+        1.0 / config.max_tick_rate as f32
+    } else {
+        time.delta_secs()
+    };
+    for event in local_events.read() {
+        history.insert(event.clone(), input_tick, frame.0, delta_time);
+        history.recent += 1;
+    }
+    history.to_send = history.recent;
+}
+
+/// Merges the inputs of `E` recorded within one estimated server tick, see
+/// [`AppPredictionExt::add_client_predicted_event_coalesced`](super::AppPredictionExt::add_client_predicted_event_coalesced).
+pub fn coalesce_predicted_inputs_system<E: Event>(
+    mut history: ResMut<PredictedEventHistory<E>>,
+    coalescing: Res<InputCoalescing<E>>,
+    config: Res<SnapshotInterpolationConfig>,
+) {
+    history.coalesce(coalescing.0, 1.0 / config.max_tick_rate as f32);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::{event::Events, schedule::IntoSystemConfigs, schedule::Schedule, world::World};
+
+    use super::*;
+
+    #[derive(Event, Clone, Debug, PartialEq)]
+    struct Move(f32);
+
+    const CLIENT: ClientId = ClientId::new(1);
+
+    fn input(tick: u32, sequence: u32) -> PredictedInput<Move> {
+        PredictedInput {
+            event: Move(1.0),
+            tick: RepliconTick::new(tick),
+            delta_time: 0.01,
+            sequence,
+        }
+    }
+
+    fn ready_sequences(queue: &ServerInputQueue<Move>) -> Vec<u32> {
+        queue.ready().map(|(_, input)| input.sequence).collect()
+    }
+
+    #[test]
+    fn queue_ignores_duplicate_and_old_sequences() {
+        let mut queue = ServerInputQueue::new(PredictedEventConfig::default());
+        queue.push(CLIENT, input(1, 0));
+        queue.push(CLIENT, input(1, 0));
+        queue.push(CLIENT, input(2, 1));
+        queue.push(CLIENT, input(1, 0));
+        queue.drain(None);
+        assert_eq!(ready_sequences(&queue), [0, 1]);
+    }
+
+    #[test]
+    fn queue_orders_inputs_by_tick() {
+        let mut queue = ServerInputQueue::new(PredictedEventConfig::default());
+        queue.push(CLIENT, input(5, 0));
+        queue.push(CLIENT, input(3, 1));
+        queue.push(CLIENT, input(5, 2));
+        queue.drain(None);
+        assert_eq!(ready_sequences(&queue), [1, 0, 2]);
+    }
+
+    #[test]
+    fn queue_releases_budget_per_tick() {
+        let mut queue = ServerInputQueue::new(PredictedEventConfig {
+            input_budget: 2,
+            ..Default::default()
+        });
+        for sequence in 0..3 {
+            queue.push(CLIENT, input(1, sequence));
+        }
+        queue.drain(None);
+        assert_eq!(ready_sequences(&queue), [0, 1]);
+        queue.drain(None);
+        assert_eq!(ready_sequences(&queue), [2]);
+    }
+
+    #[test]
+    fn queue_drops_oldest_when_full() {
+        let mut queue = ServerInputQueue::new(PredictedEventConfig {
+            input_queue_capacity: 2,
+            ..Default::default()
+        });
+        for sequence in 0..3 {
+            queue.push(CLIENT, input(sequence, sequence));
+        }
+        assert_eq!(queue.dropped_inputs(CLIENT), 1);
+        queue.drain(None);
+        assert_eq!(ready_sequences(&queue), [1, 2]);
+
+        queue.remove_client(CLIENT);
+        assert_eq!(queue.dropped_inputs(CLIENT), 0);
+    }
+
+    #[test]
+    fn queue_holds_delayed_inputs_until_their_tick() {
+        let mut queue = ServerInputQueue::new(PredictedEventConfig {
+            input_delay_ticks: 2,
+            ..Default::default()
+        });
+        queue.push(CLIENT, input(3, 0));
+        queue.push(CLIENT, input(4, 1));
+        queue.drain(Some(RepliconTick::new(2)));
+        assert!(ready_sequences(&queue).is_empty());
+        queue.drain(Some(RepliconTick::new(3)));
+        assert_eq!(ready_sequences(&queue), [0]);
+        queue.drain(Some(RepliconTick::new(4)));
+        assert_eq!(ready_sequences(&queue), [1]);
+    }
+
+    #[test]
+    fn coalesce_merges_window_into_last_sequence() {
+        let mut history = PredictedEventHistory::new();
+        for sequence in 0..3 {
+            history.insert(Move(1.0), RepliconTick::new(sequence), sequence, 0.01);
+        }
+        history.recent = 0;
+        history.open = 3;
+        history.expire_unacknowledged(0.1);
+        history.coalesce(|a, b| Move(a.0 + b.0), 0.05);
+
+        let merged: Vec<_> = history.iter().collect();
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].value, Move(3.0));
+        assert_eq!(merged[0].sequence, 2);
+        assert_eq!(merged[0].tick, RepliconTick::new(2));
+        assert!((merged[0].delta_time - 0.03).abs() < 1e-6);
+        assert_eq!(history.to_send, 1);
+    }
+
+    /// Sends `frames` inputs through a channel losing 3 of every 4 messages and returns the
+    /// sequences the server released, in order.
+    fn lossy_loopback(redundancy: usize, frames: u32) -> Vec<u32> {
+        let mut client = World::new();
+        client.insert_resource(PredictedEventHistory::<Move>::with_config(
+            PredictedEventConfig {
+                redundancy,
+                ..Default::default()
+            },
+        ));
+        client.insert_resource(EstimatedServerTick::default());
+        client.insert_resource(FrameCount::default());
+        client.insert_resource(Time::<()>::default());
+        client.insert_resource(SnapshotInterpolationConfig {
+            max_tick_rate: 30,
+            curve: Default::default(),
+            max_snapshot_age_secs: 1.0,
+            max_snapshots: 2,
+            extrapolation: Default::default(),
+        });
+        client.init_resource::<Events<Move>>();
+        client.init_resource::<Events<PredictedInputBatch<Move>>>();
+        let mut schedule = Schedule::default();
+        schedule.add_systems(
+            (
+                record_predicted_inputs_system::<Move>,
+                send_predicted_inputs_system::<Move>,
+            )
+                .chain(),
+        );
+
+        let mut queue = ServerInputQueue::new(PredictedEventConfig::default());
+        let mut released = Vec::new();
+        for frame in 0..frames {
+            client.send_event(Move(1.0));
+            schedule.run(&mut client);
+            let batches: Vec<_> = client
+                .resource_mut::<Events<PredictedInputBatch<Move>>>()
+                .drain()
+                .collect();
+            for batch in batches {
+                if frame % 4 != 3 {
+                    continue;
+                }
+                for input in batch.inputs {
+                    queue.push(CLIENT, input);
+                }
+            }
+            queue.drain(None);
+            released.extend(ready_sequences(&queue));
+        }
+        released
+    }
+
+    #[test]
+    fn redundancy_recovers_lost_inputs() {
+        assert_eq!(lossy_loopback(3, 40), (0..40).collect::<Vec<_>>());
+        assert_eq!(lossy_loopback(0, 40).len(), 10);
+    }
+}
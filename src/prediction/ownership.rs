@@ -0,0 +1,106 @@
+use bevy::ecs::{
+    change_detection::{DetectChanges, Ref},
+    entity::Entity,
+    event::{Event, EventReader, EventWriter},
+    query::{Added, Changed, Or, With},
+    system::{Commands, Query, Res, ResMut, Resource},
+};
+use bevy_replicon::{core::replicon_client::RepliconClient, prelude::ClientId};
+use std::fmt::Debug;
+
+use crate::{Interpolated, NetworkOwner};
+
+use super::{OwnerPredicted, Predicted, PredictedEventHistory};
+
+/// Sent on the client when the [`NetworkOwner`] of an owner predicted entity changed at runtime.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct OwnershipChanged {
+    pub entity: Entity,
+    /// Whether the entity is now predicted by this client.
+    pub predicted: bool,
+}
+
+/// Id of the local client, used to decide which owner predicted entities are [`Predicted`].
+///
+/// Takes precedence over the id reported by the transport through [`RepliconClient`], insert it
+/// for transports that do not report one.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LocalClientId(pub ClientId);
+
+/// Id of the local client from [`LocalClientId`], falling back to [`RepliconClient::id`].
+pub fn local_client_id(
+    local_client_id: Option<&LocalClientId>,
+    client: &RepliconClient,
+) -> Option<ClientId> {
+    local_client_id.map(|id| id.0).or_else(|| client.id())
+}
+
+/// Run condition that returns `true` once the id of the local client is known.
+pub fn local_client_id_known(
+    local_client_id_res: Option<Res<LocalClientId>>,
+    client: Res<RepliconClient>,
+) -> bool {
+    local_client_id(local_client_id_res.as_deref(), &client).is_some()
+}
+
+/// Marks owner predicted entities as [`Predicted`] for their owner and [`Interpolated`] for
+/// everyone else, re-evaluated whenever the [`NetworkOwner`] changes.
+///
+/// With authority and no client connection (singleplayer or listen server), entities owned by
+/// [`ClientId::SERVER`] are marked [`Predicted`] and all others are left untouched.
+pub fn owner_prediction_init_system(
+    q_owners: Query<
+        (Entity, Ref<NetworkOwner>),
+        (
+            With<OwnerPredicted>,
+            Or<(Added<OwnerPredicted>, Changed<NetworkOwner>)>,
+        ),
+    >,
+    local_client_id_res: Option<Res<LocalClientId>>,
+    client: Res<RepliconClient>,
+    mut ownership_changes: EventWriter<OwnershipChanged>,
+    mut commands: Commands,
+) {
+    let client_id = if client.is_connected() {
+        let Some(client_id) = local_client_id(local_client_id_res.as_deref(), &client) else {
+            return;
+        };
+        client_id
+    } else {
+        ClientId::SERVER
+    };
+    for (e, id) in q_owners.iter() {
+        let predicted = id.0 == client_id.get();
+        if predicted {
+            commands
+                .entity(e)
+                .remove::<Interpolated>()
+                .insert(Predicted);
+        } else if !client.is_connected() {
+            // Interpolated is replicated, so the host must not insert it
+            commands.entity(e).remove::<Predicted>();
+        } else {
+            commands
+                .entity(e)
+                .remove::<Predicted>()
+                .insert(Interpolated);
+        }
+        if !id.is_added() {
+            ownership_changes.send(OwnershipChanged {
+                entity: e,
+                predicted,
+            });
+        }
+    }
+}
+
+/// Clears the history of `E` after an ownership change, so the new owner starts predicting
+/// without inputs recorded for a different entity state.
+pub fn clear_history_on_ownership_change_system<E: Event>(
+    mut ownership_changes: EventReader<OwnershipChanged>,
+    mut history: ResMut<PredictedEventHistory<E>>,
+) {
+    if ownership_changes.read().count() > 0 {
+        history.clear();
+    }
+}
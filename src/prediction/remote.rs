@@ -0,0 +1,131 @@
+use bevy::{
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::Event,
+        query::{With, Without},
+        system::{Commands, Query, Res},
+    },
+    time::Time,
+    utils::HashMap,
+};
+use bevy_replicon::core::replicon_tick::RepliconTick;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use crate::{interpolation::SnapshotBuffer, Interpolated, NetworkOwner};
+
+use super::{Predict, PredictInfo, Predicted, ServerInputQueue};
+
+/// Latest input of `E` the server applied for the [`NetworkOwner`] of an entity, replicated to all
+/// clients for
+/// [`AppPredictionExt::predict_remote_entities`](super::AppPredictionExt::predict_remote_entities).
+///
+/// `None` while the owner sends no inputs.
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct RemoteInput<E> {
+    pub event: Option<E>,
+}
+
+/// Marks an [`Interpolated`] entity whose `C` is advanced with the [`RemoteInput`] of its owner
+/// instead of being interpolated.
+#[derive(Component)]
+pub struct RemotePredicted<C> {
+    /// Tick of the snapshot the remote prediction continues from.
+    pub snapshot_tick: u32,
+    marker: PhantomData<C>,
+}
+
+/// Stores the latest input of every client in the [`RemoteInput`] of its owned entities.
+pub fn server_remote_input_system<E: Event + Clone>(
+    queue: Res<ServerInputQueue<E>>,
+    mut q_owned: Query<(Entity, &NetworkOwner, Option<&mut RemoteInput<E>>)>,
+    mut commands: Commands,
+) {
+    let mut latest = HashMap::<u64, &E>::new();
+    for (client_id, input) in queue.ready() {
+        latest.insert(client_id.get(), &input.event);
+    }
+    for (entity, owner, remote_input) in &mut q_owned {
+        let event = latest.get(&owner.0).map(|&event| event.clone());
+        match remote_input {
+            Some(mut remote_input) => {
+                // Avoids replicating idle entities every tick
+                if remote_input.event.is_some() || event.is_some() {
+                    remote_input.event = event;
+                }
+            }
+            None => {
+                commands.entity(entity).insert(RemoteInput { event });
+            }
+        }
+    }
+}
+
+/// Advances `C` of [`Interpolated`] entities with the [`RemoteInput`] of their owner.
+///
+/// Whenever a new snapshot arrives, the prediction restarts from it with the time since its
+/// arrival, afterwards it continues with the frame delta.
+pub fn remote_prediction_system<E, T, C>(
+    mut q_remote: Query<
+        (
+            Entity,
+            &mut C,
+            &T,
+            &mut SnapshotBuffer<C>,
+            &RemoteInput<E>,
+            Option<&mut RemotePredicted<C>>,
+        ),
+        (With<Interpolated>, Without<Predicted>),
+    >,
+    time: Res<Time>,
+    mut commands: Commands,
+) where
+    E: Event,
+    T: Component + Clone,
+    C: Component + Predict<E, T> + Clone,
+{
+    for (entity, mut component, context, mut snapshot_buffer, remote_input, remote) in &mut q_remote
+    {
+        if snapshot_buffer.is_empty() {
+            continue;
+        }
+        let snapshot_tick = snapshot_buffer.latest_snapshot_tick();
+        let restart = remote
+            .as_ref()
+            .is_none_or(|remote| remote.snapshot_tick != snapshot_tick);
+        let delta_time = if restart {
+            *component = snapshot_buffer.latest_snapshot();
+            snapshot_buffer.age_secs()
+        } else {
+            time.delta_secs()
+        };
+        match remote {
+            Some(mut remote) => remote.snapshot_tick = snapshot_tick,
+            None => {
+                commands.entity(entity).insert(RemotePredicted::<C> {
+                    snapshot_tick,
+                    marker: PhantomData,
+                });
+            }
+        }
+        snapshot_buffer.advance(time.delta_secs());
+
+        let Some(event) = &remote_input.event else {
+            continue;
+        };
+        // Only `C` restarts from the snapshots, changes to the replicated context are discarded
+        let mut context = context.clone();
+        component.apply_event(
+            event,
+            &PredictInfo {
+                tick: RepliconTick::new(snapshot_tick),
+                delta_time,
+                is_resimulation: false,
+                sequence: 0,
+            },
+            &mut context,
+        );
+    }
+}
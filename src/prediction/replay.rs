@@ -0,0 +1,835 @@
+use bevy::{
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{Event, Events},
+        query::{QueryState, ReadOnlyQueryData, With, Without},
+        system::{Commands, Local, Query, Resource},
+        world::{EntityWorldMut, Mut, World},
+    },
+    hierarchy::DespawnRecursiveExt,
+    log::{debug, warn},
+    utils::{HashMap, HashSet},
+};
+use bevy_replicon::{client::confirm_history::ConfirmHistory, core::replicon_tick::RepliconTick};
+use std::any::{type_name, Any, TypeId};
+use std::fmt::Debug;
+use std::marker::PhantomData;
+
+use crate::{
+    interpolation::{Interpolate, SnapshotBuffer, SnapshotInterpolationConfig},
+    Interpolated,
+};
+
+use super::{
+    needs_rollback, sequence_is_newer, ContextSnapshot, EventSpawner, Predict, PredictEventSpawn,
+    PredictInfo, PredictMulti, PredictMultiInput, PredictQuery, Predicted, PredictedComponents,
+    PredictedEventHistory, PredictionError, PredictionPaused, PredictionRegistry, PredictionReset,
+    PredictionRollbackLimit, PredictionState, Reconciled, CORRECTION_EPSILON,
+};
+
+/// Type-erased access to the history of a predicted event for [`predicted_replay_system`].
+#[derive(Clone, Copy)]
+pub(super) struct EventTimeline {
+    /// Drops the events reflected in the server state of all predicted entities.
+    pub(super) trim: fn(&mut World, RepliconTick),
+    /// Index and frame of the events not reflected in the server state of a single entity.
+    pub(super) frames: fn(&World, RepliconTick) -> Vec<(usize, u32)>,
+    pub(super) replay: fn(&mut World, Entity, usize),
+    /// Frame, index and tick of the events the server acknowledged but that are not dropped yet.
+    pub(super) acknowledged: fn(&World) -> Vec<(u32, usize, RepliconTick)>,
+    /// Summed delta time of the events not reflected in the server state at a tick.
+    pub(super) pending_secs: fn(&World, RepliconTick) -> f32,
+    /// Drops all events, see [`PredictionRollbackLimit`].
+    pub(super) reset: fn(&mut World),
+    /// Index of the first event applied for the first time during the current frame.
+    pub(super) first_new: fn(&World) -> usize,
+}
+
+/// Type-erased divergence measurement of a predicted component for [`PredictionDivergenceLog`].
+#[derive(Clone, Copy)]
+pub(super) struct DivergenceCheck {
+    /// Restores the previous snapshot if a new one arrived, returns whether it did.
+    pub(super) begin: fn(&mut World, Entity) -> bool,
+    /// Compares the replayed value with the new snapshot.
+    pub(super) end: fn(&mut World, Entity, &[RepliconTick]),
+}
+
+/// Type-erased copy of a predicted component or context, taken before the replay of a
+/// [`PredictionDivergenceLog`] check and restored after it.
+#[derive(Clone, Copy)]
+pub(super) struct DivergenceBackup {
+    pub(super) save: fn(&World, Entity) -> Option<Box<dyn Any>>,
+    pub(super) restore: fn(&mut World, Entity, Box<dyn Any>),
+}
+
+/// Present while [`check_divergence`] replays inputs, replays don't spawn event entities then.
+#[derive(Resource)]
+struct DivergenceReplay;
+
+pub(super) fn spawn_event_entity<E, T, C>(
+    component: &C,
+    event: &E,
+    info: &PredictInfo,
+    context: &T,
+    commands: &mut Commands,
+    existing: Option<Entity>,
+) -> Option<Entity>
+where
+    E: Event,
+    C: PredictEventSpawn<E, T>,
+{
+    let bundle = component.spawn_for_event(event, info, context)?;
+    match existing {
+        Some(entity) => {
+            commands.entity(entity).insert(bundle);
+            Some(entity)
+        }
+        None => Some(commands.spawn(bundle).id()),
+    }
+}
+
+/// Entities spawned on the client by replays of [`PredictEventSpawn`], keyed by the predicted
+/// entity, the event type and the input sequence.
+#[derive(Resource, Default)]
+pub(super) struct PredictedEventSpawns {
+    spawned: HashMap<(Entity, TypeId, u32), Entity>,
+    /// Keys produced by the replays of the current frame.
+    produced: HashSet<(Entity, TypeId, u32)>,
+}
+
+/// Insert this resource to log how far replayed predictions diverge from the server state,
+/// e.g. to tell mismatching `apply_event` math from packet timing.
+///
+/// For every new snapshot of a predicted entity, the inputs the server acknowledged since the
+/// previous snapshot are replayed on top of it and compared with the new snapshot.
+/// Divergences are sent as [`PredictionDivergence`] and logged with `debug!`.
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct PredictionDivergenceLog;
+
+/// Difference between the replayed prediction of `C` and the new server snapshot,
+/// see [`PredictionDivergenceLog`].
+#[derive(Event, Debug)]
+pub struct PredictionDivergence<C: Component> {
+    pub entity: Entity,
+    /// Tick of the new snapshot.
+    pub tick: u32,
+    /// [`Interpolate::distance`] between the replayed and the server value.
+    pub error: f32,
+    /// Ticks of the replayed inputs in replay order, one of them introduced the divergence.
+    pub input_ticks: Vec<RepliconTick>,
+    marker: PhantomData<C>,
+}
+
+/// Latest snapshot tick of `C` checked for divergence.
+#[derive(Component)]
+pub(super) struct DivergenceTrace<C> {
+    snapshot_tick: u32,
+    marker: PhantomData<C>,
+}
+
+/// Restores a predicted component to its latest snapshot before any events are replayed.
+///
+/// The value from before the rollback is kept in [`PredictionState`] to measure corrections.
+/// Entities without a snapshot yet, e.g. when the first update after the spawn is delayed, keep
+/// their current value and only record inputs until the first snapshot arrives. Entities without
+/// a new snapshot since their last rollback keep their current value, see [`Reconciled`].
+pub fn rollback_component_system<C: Component + Interpolate + Clone>(
+    mut q_predicted: Query<
+        (
+            Entity,
+            &mut C,
+            &SnapshotBuffer<C>,
+            Option<&mut PredictionState<C>>,
+            Option<&ConfirmHistory>,
+            Option<&Reconciled>,
+        ),
+        (
+            With<Predicted>,
+            Without<Interpolated>,
+            Without<PredictionPaused>,
+        ),
+    >,
+    mut commands: Commands,
+) {
+    for (entity, mut component, snapshot_buffer, state, confirmed, reconciled) in &mut q_predicted {
+        if snapshot_buffer.is_empty() {
+            continue;
+        }
+        let rollback = needs_rollback(confirmed, reconciled);
+        match state {
+            Some(mut state) => state.uncorrected = component.clone(),
+            None => {
+                commands.entity(entity).insert(PredictionState {
+                    uncorrected: component.clone(),
+                });
+            }
+        }
+        if rollback {
+            *component = snapshot_buffer.latest_snapshot();
+        }
+    }
+}
+
+/// Restores a prediction context to its latest server state before any events are replayed.
+pub fn rollback_context_system<T: Component + Clone>(
+    mut q_predicted: Query<
+        (
+            Entity,
+            &mut T,
+            Option<&ContextSnapshot<T>>,
+            Option<&ConfirmHistory>,
+            Option<&Reconciled>,
+        ),
+        (
+            With<Predicted>,
+            Without<Interpolated>,
+            Without<PredictionPaused>,
+        ),
+    >,
+    mut commands: Commands,
+) {
+    for (entity, mut context, context_snapshot, confirmed, reconciled) in &mut q_predicted {
+        if !needs_rollback(confirmed, reconciled) {
+            continue;
+        }
+        match context_snapshot {
+            Some(context_snapshot) => *context = context_snapshot.0.clone(),
+            // Not replicated, the current value is the best known state.
+            None => {
+                commands
+                    .entity(entity)
+                    .insert(ContextSnapshot(context.clone()));
+            }
+        }
+    }
+}
+
+/// Replays the histories of all predicted events once across all components they predict.
+///
+/// Runs after every predicted component of an entity was rolled back, so each event sees
+/// the state the previous events produced in all of them. Events of different types are
+/// replayed in the frame they were recorded in, events of the same frame in registration order
+/// of their types and events of the same type in recording order, so replays match the live
+/// application order.
+///
+/// Entities without a [`ConfirmHistory`] yet are replayed on top of their latest snapshot as well,
+/// relying on [`InputAck`](super::InputAck) to drop applied inputs, and a warning is logged once
+/// per entity.
+pub fn predicted_replay_system(
+    world: &mut World,
+    q_predicted: &mut QueryState<
+        (Entity, Option<&ConfirmHistory>, Option<&Reconciled>),
+        (
+            With<Predicted>,
+            Without<Interpolated>,
+            Without<PredictionPaused>,
+        ),
+    >,
+    mut unconfirmed: Local<HashSet<Entity>>,
+) {
+    let mut predicted: Vec<(Entity, RepliconTick)> = Vec::new();
+    let mut rolled_back = HashSet::new();
+    for (entity, confirmed, reconciled) in q_predicted.iter(world) {
+        if needs_rollback(confirmed, reconciled) {
+            rolled_back.insert(entity);
+        }
+        match confirmed {
+            Some(confirmed) => {
+                unconfirmed.remove(&entity);
+                predicted.push((entity, confirmed.last_tick()));
+            }
+            None => {
+                if unconfirmed.insert(entity) {
+                    warn!(
+                        "predicted entity {entity} has no `ConfirmHistory`, \
+                        replaying without a confirmed tick"
+                    );
+                }
+                predicted.push((entity, RepliconTick::new(0)));
+            }
+        }
+    }
+    let timelines = world
+        .get_resource::<PredictionRegistry>()
+        .map(|registry| registry.timelines.clone())
+        .unwrap_or_default();
+    if world.contains_resource::<PredictionDivergenceLog>() {
+        let entities: Vec<Entity> = predicted.iter().map(|(entity, _)| *entity).collect();
+        check_divergence(world, &entities, &timelines);
+    }
+    // Entities can be confirmed at different ticks, only drop events none of them still needs
+    if let Some(oldest_tick) = predicted
+        .iter()
+        .map(|&(_, tick)| tick)
+        .reduce(|oldest, tick| if tick < oldest { tick } else { oldest })
+    {
+        for event_timeline in &timelines {
+            (event_timeline.trim)(world, oldest_tick);
+        }
+    }
+    if exceeds_rollback_limit(world, &predicted, &timelines) {
+        for event_timeline in &timelines {
+            (event_timeline.reset)(world);
+        }
+        for &(entity, _) in &predicted {
+            // Rolls back to the latest snapshot in the next frame
+            world.entity_mut(entity).remove::<Reconciled>();
+            world.send_event(PredictionReset { entity });
+        }
+        return;
+    }
+    let predicted_entities: HashSet<Entity> = predicted.iter().map(|&(entity, _)| entity).collect();
+    for (entity, tick) in predicted {
+        let rollback = rolled_back.contains(&entity);
+        let mut timeline = Vec::new();
+        for (type_index, event_timeline) in timelines.iter().enumerate() {
+            let frames = (event_timeline.frames)(world, tick);
+            // Without a rollback only new events are applied on top of the current values
+            let first = if rollback {
+                0
+            } else {
+                (event_timeline.first_new)(world)
+            };
+            timeline.extend(
+                frames
+                    .into_iter()
+                    .filter(|&(index, _)| index >= first)
+                    .map(|(index, frame)| (frame, type_index, index)),
+            );
+        }
+        timeline.sort_unstable();
+        for (_, type_index, index) in timeline {
+            (timelines[type_index].replay)(world, entity, index);
+        }
+        world.entity_mut(entity).insert(Reconciled {
+            tick,
+            rolled_back: rollback,
+        });
+    }
+    despawn_stale_event_spawns(world, &predicted_entities, &rolled_back);
+}
+
+/// Whether the inputs not reflected in the newest confirmed tick cover more than the
+/// [`PredictionRollbackLimit`].
+pub(super) fn exceeds_rollback_limit(
+    world: &mut World,
+    predicted: &[(Entity, RepliconTick)],
+    timelines: &[EventTimeline],
+) -> bool {
+    let Some(limit) = world.get_resource::<PredictionRollbackLimit>().copied() else {
+        return false;
+    };
+    let Some(newest_tick) = predicted
+        .iter()
+        .map(|&(_, tick)| tick)
+        .reduce(|newest, tick| if tick > newest { tick } else { newest })
+    else {
+        return false;
+    };
+    let tick_duration = 1.0
+        / world
+            .resource::<SnapshotInterpolationConfig>()
+            .max_tick_rate as f32;
+    let max_secs = limit.max_ticks as f32 * tick_duration;
+    timelines
+        .iter()
+        .any(|event_timeline| (event_timeline.pending_secs)(world, newest_tick) > max_secs)
+}
+
+/// Replays the acknowledged inputs on top of the previous snapshot of every entity that received
+/// a new one, before they are dropped from the histories.
+///
+/// All predicted components and contexts of the entity are restored afterwards, so the check
+/// doesn't affect the reconciled values.
+pub(super) fn check_divergence(
+    world: &mut World,
+    entities: &[Entity],
+    timelines: &[EventTimeline],
+) {
+    let mut acknowledged: Vec<(u32, usize, usize, RepliconTick)> = Vec::new();
+    for (type_index, event_timeline) in timelines.iter().enumerate() {
+        acknowledged.extend(
+            (event_timeline.acknowledged)(world)
+                .into_iter()
+                .map(|(frame, index, tick)| (frame, type_index, index, tick)),
+        );
+    }
+    if acknowledged.is_empty() {
+        return;
+    }
+    acknowledged.sort_unstable_by_key(|&(frame, type_index, index, _)| (frame, type_index, index));
+    let input_ticks: Vec<RepliconTick> = acknowledged.iter().map(|&(.., tick)| tick).collect();
+
+    let Some(registry) = world.get_resource::<PredictionRegistry>() else {
+        return;
+    };
+    let checks = registry.divergence_checks.clone();
+    let backups = registry.divergence_backups.clone();
+    world.insert_resource(DivergenceReplay);
+    for &entity in entities {
+        let saved: Vec<(DivergenceBackup, Box<dyn Any>)> = backups
+            .iter()
+            .filter_map(|backup| (backup.save)(world, entity).map(|value| (*backup, value)))
+            .collect();
+        let started: Vec<DivergenceCheck> = checks
+            .iter()
+            .copied()
+            .filter(|check| (check.begin)(world, entity))
+            .collect();
+        if !started.is_empty() {
+            for &(_, type_index, index, _) in &acknowledged {
+                (timelines[type_index].replay)(world, entity, index);
+            }
+            for check in started {
+                (check.end)(world, entity, &input_ticks);
+            }
+        }
+        for (backup, value) in saved {
+            (backup.restore)(world, entity, value);
+        }
+    }
+    world.remove_resource::<DivergenceReplay>();
+}
+
+pub(super) fn divergence_begin<C: Component + Interpolate + Clone>(
+    world: &mut World,
+    entity: Entity,
+) -> bool {
+    let mut entity = world.entity_mut(entity);
+    let Some(snapshot_buffer) = entity.get::<SnapshotBuffer<C>>() else {
+        return false;
+    };
+    let snapshot_tick = snapshot_buffer.latest_snapshot_tick();
+    let len = snapshot_buffer.len();
+    if len < 2 {
+        return false;
+    }
+    let previous = snapshot_buffer.buffer[len - 2].value.clone();
+    match entity.get_mut::<DivergenceTrace<C>>() {
+        Some(trace) if trace.snapshot_tick == snapshot_tick => return false,
+        Some(mut trace) => trace.snapshot_tick = snapshot_tick,
+        None => {
+            entity.insert(DivergenceTrace::<C> {
+                snapshot_tick,
+                marker: PhantomData,
+            });
+        }
+    }
+    let Some(mut component) = entity.get_mut::<C>() else {
+        return false;
+    };
+    *component = previous;
+    true
+}
+
+pub(super) fn divergence_end<C: Component + Interpolate + Clone>(
+    world: &mut World,
+    entity: Entity,
+    input_ticks: &[RepliconTick],
+) {
+    let entity_ref = world.entity(entity);
+    let Some(snapshot_buffer) = entity_ref.get::<SnapshotBuffer<C>>() else {
+        return;
+    };
+    let tick = snapshot_buffer.latest_snapshot_tick();
+    let latest = snapshot_buffer.latest_snapshot();
+    let Some(component) = entity_ref.get::<C>() else {
+        return;
+    };
+    let error = component.distance(&latest);
+    if error <= CORRECTION_EPSILON {
+        return;
+    }
+
+    debug!(
+        "{} of {entity} diverged by {error} from snapshot {tick} \
+        after replaying inputs of ticks {input_ticks:?}",
+        type_name::<C>()
+    );
+    if let Some(mut divergences) = world.get_resource_mut::<Events<PredictionDivergence<C>>>() {
+        divergences.send(PredictionDivergence {
+            entity,
+            tick,
+            error,
+            input_ticks: input_ticks.to_vec(),
+            marker: PhantomData,
+        });
+    }
+}
+
+pub(super) fn divergence_save<T: Component + Clone>(
+    world: &World,
+    entity: Entity,
+) -> Option<Box<dyn Any>> {
+    let value = world.get::<T>(entity)?.clone();
+    Some(Box::new(value))
+}
+
+pub(super) fn divergence_restore<T: Component + Clone>(
+    world: &mut World,
+    entity: Entity,
+    value: Box<dyn Any>,
+) {
+    let Ok(value) = value.downcast::<T>() else {
+        return;
+    };
+    if let Some(mut current) = world.get_mut::<T>(entity) {
+        *current = *value;
+    }
+}
+
+pub(super) fn timeline_trim<E: Event>(world: &mut World, oldest_tick: RepliconTick) {
+    world
+        .resource_mut::<PredictedEventHistory<E>>()
+        .remove_stale(oldest_tick);
+}
+
+/// Returns the index and the frame of the events of `E` an entity confirmed at `tick` replays.
+pub(super) fn timeline_frames<E: Event>(world: &World, tick: RepliconTick) -> Vec<(usize, u32)> {
+    world
+        .resource::<PredictedEventHistory<E>>()
+        .unconfirmed(tick)
+        .map(|(index, event_snapshot)| (index, event_snapshot.frame))
+        .collect()
+}
+
+pub(super) fn timeline_pending_secs<E: Event>(world: &World, tick: RepliconTick) -> f32 {
+    world
+        .resource::<PredictedEventHistory<E>>()
+        .unconfirmed(tick)
+        .map(|(_, event_snapshot)| event_snapshot.delta_time)
+        .sum()
+}
+
+pub(super) fn timeline_reset<E: Event>(world: &mut World) {
+    world.resource_mut::<PredictedEventHistory<E>>().clear();
+}
+
+pub(super) fn timeline_first_new<E: Event>(world: &World) -> usize {
+    world.resource::<PredictedEventHistory<E>>().first_new()
+}
+
+pub(super) fn timeline_acknowledged<E: Event>(world: &World) -> Vec<(u32, usize, RepliconTick)> {
+    let history = world.resource::<PredictedEventHistory<E>>();
+    let Some(acknowledged) = history.acknowledged else {
+        return Vec::new();
+    };
+    history
+        .events
+        .iter()
+        .enumerate()
+        .take_while(|(_, event_snapshot)| !sequence_is_newer(event_snapshot.sequence, acknowledged))
+        .map(|(index, event_snapshot)| (event_snapshot.frame, index, event_snapshot.tick))
+        .collect()
+}
+
+/// Replays the remaining event of `E` at `index` on all components it predicts.
+pub(super) fn timeline_replay<E: Event>(world: &mut World, entity: Entity, index: usize) {
+    let replays = world.resource::<PredictedComponents<E>>().replays.clone();
+    world.resource_scope(|world, history: Mut<PredictedEventHistory<E>>| {
+        let Some(event_snapshot) = history.events.get(index) else {
+            return;
+        };
+        let info = PredictInfo {
+            tick: event_snapshot.tick,
+            delta_time: event_snapshot.delta_time,
+            // Events recorded or, if delayed, due this frame are applied for the first time
+            is_resimulation: index < history.first_new(),
+            sequence: event_snapshot.sequence,
+        };
+        for replay in &replays {
+            replay(world, entity, &event_snapshot.value, &info);
+        }
+    });
+}
+
+/// Whether `C` of the entity received at least one snapshot to replay inputs on.
+pub(super) fn has_snapshot<C: Component + Interpolate + Clone>(entity: &EntityWorldMut) -> bool {
+    entity
+        .get::<SnapshotBuffer<C>>()
+        .is_some_and(|snapshot_buffer| !snapshot_buffer.is_empty())
+}
+
+/// Applies a single replayed event to `C`.
+///
+/// Events recorded this frame also continue the uncorrected prediction and the smoothing origin.
+pub(super) fn replay_event<E, T, C>(
+    world: &mut World,
+    entity: Entity,
+    event: &E,
+    info: &PredictInfo,
+) where
+    E: Event,
+    T: Component + Clone,
+    C: Component + Predict<E, T> + Clone,
+{
+    let spawner = world
+        .get_resource::<EventSpawner<E, T, C>>()
+        .filter(|_| !world.contains_resource::<DivergenceReplay>())
+        .map(|spawner| spawner.0);
+    let mut entity = world.entity_mut(entity);
+    if !has_snapshot::<C>(&entity) {
+        return;
+    }
+    let Some(mut context) = entity.get::<T>().cloned() else {
+        return;
+    };
+
+    if !info.is_resimulation {
+        // Bookkeeping only, hidden from implementations as a resimulation
+        let bookkeeping = PredictInfo {
+            is_resimulation: true,
+            ..*info
+        };
+        if let Some(mut state) = entity.get_mut::<PredictionState<C>>() {
+            state
+                .uncorrected
+                .apply_event(event, &bookkeeping, &mut context.clone());
+        }
+        if let Some(mut error) = entity.get_mut::<PredictionError<C>>() {
+            error
+                .from
+                .apply_event(event, &bookkeeping, &mut context.clone());
+        }
+    }
+
+    let Some(mut component) = entity.get_mut::<C>() else {
+        return;
+    };
+    component.apply_event(event, info, &mut context);
+    let spawn = spawner.map(|spawner| (spawner, component.clone(), context.clone()));
+    if let Some(mut current_context) = entity.get_mut::<T>() {
+        *current_context = context;
+    }
+
+    if let Some((spawner, component, context)) = spawn {
+        let entity = entity.id();
+        replay_event_spawn(world, entity, spawner, &component, event, info, &context);
+    }
+}
+
+/// Applies a single replayed event to `C` of a [`PredictQuery`] registration.
+///
+/// The query data is fetched again for every application, so `C` is updated on a copy.
+pub(super) fn replay_event_query<E, Q, C>(
+    world: &mut World,
+    entity: Entity,
+    event: &E,
+    info: &PredictInfo,
+) where
+    E: Event,
+    Q: ReadOnlyQueryData,
+    C: Component + PredictQuery<E, Q> + Clone,
+{
+    let mut entity = world.entity_mut(entity);
+    if !has_snapshot::<C>(&entity) {
+        return;
+    }
+    let Some(mut component) = entity.get::<C>().cloned() else {
+        return;
+    };
+
+    if !info.is_resimulation {
+        // Bookkeeping only, hidden from implementations as a resimulation
+        let bookkeeping = PredictInfo {
+            is_resimulation: true,
+            ..*info
+        };
+        let mut uncorrected = entity
+            .get::<PredictionState<C>>()
+            .map(|state| state.uncorrected.clone());
+        let mut from = entity
+            .get::<PredictionError<C>>()
+            .map(|error| error.from.clone());
+        if let (Some(uncorrected), Some(query)) = (&mut uncorrected, entity.get_components::<Q>()) {
+            uncorrected.apply_event(event, &bookkeeping, query);
+        }
+        if let (Some(from), Some(query)) = (&mut from, entity.get_components::<Q>()) {
+            from.apply_event(event, &bookkeeping, query);
+        }
+        if let (Some(uncorrected), Some(mut state)) =
+            (uncorrected, entity.get_mut::<PredictionState<C>>())
+        {
+            state.uncorrected = uncorrected;
+        }
+        if let (Some(from), Some(mut error)) = (from, entity.get_mut::<PredictionError<C>>()) {
+            error.from = from;
+        }
+    }
+
+    let Some(query) = entity.get_components::<Q>() else {
+        return;
+    };
+    component.apply_event(event, info, query);
+    if let Some(mut current) = entity.get_mut::<C>() {
+        *current = component;
+    }
+}
+
+/// Spawns or updates the entity of a replayed event with [`PredictEventSpawn`].
+pub(super) fn replay_event_spawn<E: Event, T, C>(
+    world: &mut World,
+    entity: Entity,
+    spawner: fn(&C, &E, &PredictInfo, &T, &mut Commands, Option<Entity>) -> Option<Entity>,
+    component: &C,
+    event: &E,
+    info: &PredictInfo,
+    context: &T,
+) {
+    let key = (entity, TypeId::of::<E>(), info.sequence);
+    let existing = world
+        .resource::<PredictedEventSpawns>()
+        .spawned
+        .get(&key)
+        .copied()
+        .filter(|&spawned| world.entities().contains(spawned));
+    let spawned = spawner(
+        component,
+        event,
+        info,
+        context,
+        &mut world.commands(),
+        existing,
+    );
+    world.flush();
+    let mut spawns = world.resource_mut::<PredictedEventSpawns>();
+    match spawned {
+        Some(spawned) => {
+            spawns.spawned.insert(key, spawned);
+            spawns.produced.insert(key);
+        }
+        None => {
+            spawns.produced.remove(&key);
+        }
+    }
+}
+
+/// Despawns the entities of events a rollback no longer produced and of entities that are no
+/// longer predicted.
+pub(super) fn despawn_stale_event_spawns(
+    world: &mut World,
+    predicted: &HashSet<Entity>,
+    rolled_back: &HashSet<Entity>,
+) {
+    let Some(mut spawns) = world.get_resource_mut::<PredictedEventSpawns>() else {
+        return;
+    };
+    let PredictedEventSpawns { spawned, produced } = &mut *spawns;
+    let mut stale = Vec::new();
+    spawned.retain(|key, spawned| {
+        let (entity, ..) = *key;
+        let keep = predicted.contains(&entity)
+            && (!rolled_back.contains(&entity) || produced.contains(key));
+        if !keep {
+            stale.push(*spawned);
+        }
+        keep
+    });
+    produced.clear();
+    for spawned in stale {
+        if world.entities().contains(spawned) {
+            world.commands().entity(spawned).despawn_recursive();
+        }
+    }
+    world.flush();
+}
+
+pub(super) fn reset_event_spawns(world: &mut World) {
+    despawn_stale_event_spawns(world, &HashSet::new(), &HashSet::new());
+}
+
+/// Applies a single replayed event to both components of a [`PredictMulti`] registration.
+pub(super) fn replay_event_multi<E, T, C1, C2>(
+    world: &mut World,
+    entity: Entity,
+    event: &E,
+    info: &PredictInfo,
+) where
+    E: Event,
+    T: Component + Clone,
+    C1: Component + PredictMulti<E, C2, T> + Clone,
+    C2: Component + Interpolate + Clone,
+{
+    let mut entity = world.entity_mut(entity);
+    if !has_snapshot::<C1>(&entity) || !has_snapshot::<C2>(&entity) {
+        return;
+    }
+    let (Some(mut first), Some(mut second), Some(mut context)) = (
+        entity.get::<C1>().cloned(),
+        entity.get::<C2>().cloned(),
+        entity.get::<T>().cloned(),
+    ) else {
+        return;
+    };
+
+    if !info.is_resimulation {
+        let bookkeeping = PredictInfo {
+            is_resimulation: true,
+            ..*info
+        };
+        if let (Some(first_state), Some(second_state)) = (
+            entity.get::<PredictionState<C1>>(),
+            entity.get::<PredictionState<C2>>(),
+        ) {
+            let mut uncorrected_first = first_state.uncorrected.clone();
+            let mut uncorrected_second = second_state.uncorrected.clone();
+            C1::apply_event(
+                PredictMultiInput {
+                    first: &mut uncorrected_first,
+                    second: &mut uncorrected_second,
+                },
+                event,
+                &bookkeeping,
+                &mut context.clone(),
+            );
+            if let Some(mut state) = entity.get_mut::<PredictionState<C1>>() {
+                state.uncorrected = uncorrected_first;
+            }
+            if let Some(mut state) = entity.get_mut::<PredictionState<C2>>() {
+                state.uncorrected = uncorrected_second;
+            }
+        }
+
+        let first_error = entity.get::<PredictionError<C1>>().map(|e| e.from.clone());
+        let second_error = entity.get::<PredictionError<C2>>().map(|e| e.from.clone());
+        if first_error.is_some() || second_error.is_some() {
+            let mut from_first = first_error.unwrap_or_else(|| first.clone());
+            let mut from_second = second_error.unwrap_or_else(|| second.clone());
+            C1::apply_event(
+                PredictMultiInput {
+                    first: &mut from_first,
+                    second: &mut from_second,
+                },
+                event,
+                &bookkeeping,
+                &mut context.clone(),
+            );
+            if let Some(mut error) = entity.get_mut::<PredictionError<C1>>() {
+                error.from = from_first;
+            }
+            if let Some(mut error) = entity.get_mut::<PredictionError<C2>>() {
+                error.from = from_second;
+            }
+        }
+    }
+
+    C1::apply_event(
+        PredictMultiInput {
+            first: &mut first,
+            second: &mut second,
+        },
+        event,
+        info,
+        &mut context,
+    );
+    if let Some(mut component) = entity.get_mut::<C1>() {
+        *component = first;
+    }
+    if let Some(mut component) = entity.get_mut::<C2>() {
+        *component = second;
+    }
+    if let Some(mut current_context) = entity.get_mut::<T>() {
+        *current_context = context;
+    }
+}
@@ -0,0 +1,97 @@
+use bevy::ecs::{
+    change_detection::DetectChanges,
+    component::Tick,
+    event::Event,
+    query::With,
+    system::{Query, Res, ResMut, Resource},
+    world::World,
+};
+use bevy_replicon::{client::confirm_history::ConfirmHistory, core::replicon_tick::RepliconTick};
+
+use crate::interpolation::SnapshotInterpolationConfig;
+
+use super::{
+    latest_confirmed_tick, PredictInfo, PredictResource, Predicted, PredictedEventHistory,
+    ServerInputQueue,
+};
+
+/// Latest server state of a predicted resource.
+///
+/// Captured whenever `R` is changed by anything other than the prediction itself,
+/// e.g. by a server event carrying its state.
+#[derive(Resource)]
+pub struct ResourceSnapshotBuffer<R: Resource> {
+    pub latest: Option<R>,
+    /// Change tick of the last predicted value, to tell it apart from server updates.
+    predicted_change: Tick,
+}
+
+impl<R: Resource> Default for ResourceSnapshotBuffer<R> {
+    fn default() -> Self {
+        Self {
+            latest: None,
+            predicted_change: Tick::new(0),
+        }
+    }
+}
+
+/// Server implementation for resources, applies the inputs of all clients.
+pub fn server_resource_update_system<E: Event, R: Resource + PredictResource<E>>(
+    queue: Res<ServerInputQueue<E>>,
+    config: Res<SnapshotInterpolationConfig>,
+    mut resource: ResMut<R>,
+) {
+    let tick_duration = 1.0 / config.max_tick_rate as f32;
+    for (_, input) in queue.ready() {
+        resource.apply_event(&input.event, &input.info(tick_duration));
+    }
+}
+
+/// Records the server state of `R` whenever it was not changed by the prediction.
+pub fn resource_snapshot_system<R: Resource + Clone>(
+    resource: Option<Res<R>>,
+    mut snapshot_buffer: ResMut<ResourceSnapshotBuffer<R>>,
+) {
+    let Some(resource) = resource else {
+        return;
+    };
+    if resource.is_changed() && resource.last_changed() != snapshot_buffer.predicted_change {
+        snapshot_buffer.latest = Some(R::clone(&resource));
+    }
+}
+
+/// Client prediction for resources, mirrors the replay for components.
+///
+/// Restores `R` to its latest server state and replays all events of `E` not yet reflected in it.
+pub fn predicted_resource_update_system<E: Event, R: Resource + PredictResource<E> + Clone>(
+    resource: Option<ResMut<R>>,
+    mut snapshot_buffer: ResMut<ResourceSnapshotBuffer<R>>,
+    mut history: ResMut<PredictedEventHistory<E>>,
+    q_predicted: Query<&ConfirmHistory, With<Predicted>>,
+) {
+    let (Some(mut resource), Some(latest)) = (resource, snapshot_buffer.latest.clone()) else {
+        return;
+    };
+    let tick = latest_confirmed_tick(&q_predicted).unwrap_or(RepliconTick::new(0));
+    history.remove_stale(tick);
+    let due_len = history.due_len();
+    let first_new = history.first_new();
+
+    *resource = latest;
+    for (index, event_snapshot) in history.events.iter().take(due_len).enumerate() {
+        let info = PredictInfo {
+            tick: event_snapshot.tick,
+            delta_time: event_snapshot.delta_time,
+            is_resimulation: index < first_new,
+            sequence: event_snapshot.sequence,
+        };
+        resource.apply_event(&event_snapshot.value, &info);
+    }
+    snapshot_buffer.predicted_change = resource.last_changed();
+}
+
+pub(super) fn reset_resource_snapshot<R: Resource>(world: &mut World) {
+    if let Some(mut snapshot_buffer) = world.get_resource_mut::<ResourceSnapshotBuffer<R>>() {
+        *snapshot_buffer = ResourceSnapshotBuffer::default();
+    }
+}
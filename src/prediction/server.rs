@@ -0,0 +1,162 @@
+use bevy::{
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{Event, EventReader},
+        query::ReadOnlyQueryData,
+        system::{Commands, Query, Res},
+    },
+    log::debug,
+    utils::HashMap,
+};
+use std::any::type_name;
+
+use crate::{
+    interpolation::{Interpolate, SnapshotBuffer, SnapshotInterpolationConfig},
+    NetworkOwner,
+};
+
+use super::{
+    EventSpawner, EventValidation, Predict, PredictMulti, PredictMultiInput, PredictQuery,
+    Reconciled, ServerCorrection, ServerInputQueue,
+};
+
+/// Server implementation
+///
+/// Also moves the host's own entities, which are [`Predicted`](super::Predicted) on a listen
+/// server.
+pub fn server_update_system<
+    E: Event,
+    T: Component,
+    C: Component + Interpolate + Predict<E, T> + Clone,
+>(
+    queue: Res<ServerInputQueue<E>>,
+    config: Res<SnapshotInterpolationConfig>,
+    validation: Option<Res<EventValidation<E, T, C>>>,
+    spawner: Option<Res<EventSpawner<E, T, C>>>,
+    mut subjects: Query<(Entity, &NetworkOwner, &mut C, &mut T)>,
+    mut commands: Commands,
+) {
+    if queue.ready().next().is_none() {
+        return;
+    }
+    // A single input can never account for more than a whole tick.
+    let tick_duration = 1.0 / config.max_tick_rate as f32;
+    let owned = entities_by_owner(subjects.iter().map(|(entity, owner, ..)| (entity, owner)));
+    for (client_id, input) in queue.ready() {
+        for &entity in owned.get(&client_id.get()).into_iter().flatten() {
+            let Ok((_, _, mut component, mut context)) = subjects.get_mut(entity) else {
+                continue;
+            };
+            let validated;
+            let event = match &validation {
+                Some(validation) => match (validation.0)(&input.event, &component, &context) {
+                    Some(event) => {
+                        validated = event;
+                        &validated
+                    }
+                    None => {
+                        debug!(
+                            "rejected input {} of {} from client {client_id:?}",
+                            input.sequence,
+                            type_name::<E>()
+                        );
+                        continue;
+                    }
+                },
+                None => &input.event,
+            };
+            let info = input.info(tick_duration);
+            component.apply_event(event, &info, &mut context);
+            if let Some(spawner) = &spawner {
+                (spawner.0)(&component, event, &info, &context, &mut commands, None);
+            }
+        }
+    }
+}
+
+/// Server implementation for [`PredictQuery`] registrations.
+pub fn server_query_update_system<E, Q, C>(
+    queue: Res<ServerInputQueue<E>>,
+    config: Res<SnapshotInterpolationConfig>,
+    mut subjects: Query<(Entity, &NetworkOwner, &mut C, Q)>,
+) where
+    E: Event,
+    Q: ReadOnlyQueryData + 'static,
+    C: Component + PredictQuery<E, Q>,
+{
+    if queue.ready().next().is_none() {
+        return;
+    }
+    // A single input can never account for more than a whole tick.
+    let tick_duration = 1.0 / config.max_tick_rate as f32;
+    let owned = entities_by_owner(subjects.iter().map(|(entity, owner, ..)| (entity, owner)));
+    for (client_id, input) in queue.ready() {
+        for &entity in owned.get(&client_id.get()).into_iter().flatten() {
+            let Ok((_, _, mut component, query)) = subjects.get_mut(entity) else {
+                continue;
+            };
+            component.apply_event(&input.event, &input.info(tick_duration), query);
+        }
+    }
+}
+
+/// Groups entities by the client in their [`NetworkOwner`], so inputs are dispatched to the
+/// owned entities without scanning all entities for every input.
+pub(super) fn entities_by_owner<'a>(
+    owners: impl Iterator<Item = (Entity, &'a NetworkOwner)>,
+) -> HashMap<u64, Vec<Entity>> {
+    let mut owned = HashMap::<u64, Vec<Entity>>::new();
+    for (entity, owner) in owners {
+        owned.entry(owner.0).or_default().push(entity);
+    }
+    owned
+}
+
+/// Writes received server corrections into the latest snapshot of the corrected component.
+pub fn server_correction_system<E: ServerCorrection>(
+    mut corrections: EventReader<E>,
+    mut q_buffers: Query<&mut SnapshotBuffer<E::Component>>,
+    mut commands: Commands,
+) {
+    for correction in corrections.read() {
+        if let Ok(mut snapshot_buffer) = q_buffers.get_mut(correction.entity()) {
+            snapshot_buffer.correct_latest(correction.corrected());
+            commands.entity(correction.entity()).remove::<Reconciled>();
+        }
+    }
+}
+
+/// Server implementation for [`PredictMulti`].
+pub fn server_update_multi_system<
+    E: Event,
+    T: Component,
+    C1: PredictMulti<E, C2, T>,
+    C2: Component + Interpolate,
+>(
+    queue: Res<ServerInputQueue<E>>,
+    config: Res<SnapshotInterpolationConfig>,
+    mut subjects: Query<(Entity, &NetworkOwner, &mut C1, &mut C2, &mut T)>,
+) {
+    if queue.ready().next().is_none() {
+        return;
+    }
+    let tick_duration = 1.0 / config.max_tick_rate as f32;
+    let owned = entities_by_owner(subjects.iter().map(|(entity, owner, ..)| (entity, owner)));
+    for (client_id, input) in queue.ready() {
+        for &entity in owned.get(&client_id.get()).into_iter().flatten() {
+            let Ok((_, _, mut first, mut second, mut context)) = subjects.get_mut(entity) else {
+                continue;
+            };
+            C1::apply_event(
+                PredictMultiInput {
+                    first: &mut first,
+                    second: &mut second,
+                },
+                &input.event,
+                &input.info(tick_duration),
+                &mut context,
+            );
+        }
+    }
+}
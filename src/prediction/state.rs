@@ -0,0 +1,110 @@
+use bevy::{
+    ecs::{
+        component::Component,
+        entity::Entity,
+        query::{With, Without},
+        system::{Commands, Query, Res, Resource},
+    },
+    time::Time,
+};
+use std::marker::PhantomData;
+
+use crate::{
+    interpolation::{Interpolate, SnapshotBuffer},
+    Interpolated, NetworkOwner,
+};
+
+use super::{Predicted, PredictionError, PredictionPaused, PredictionState, Reconciled};
+
+/// Predicts the next value of `C` from its current value and the frame delta, registered with
+/// [`AppPredictionExt::predict_component_from_state`](super::AppPredictionExt::predict_component_from_state).
+#[derive(Resource)]
+pub struct StatePrediction<C>(pub(super) Box<dyn Fn(&C, f32) -> C + Send + Sync>);
+
+/// Frame deltas [`StatePrediction`] was applied with since the latest snapshot of `C`.
+#[derive(Component)]
+pub struct StatePredictionSteps<C: Component> {
+    /// Tick of the snapshot the steps are applied on top of.
+    pub snapshot_tick: u32,
+    pub deltas: Vec<f32>,
+    marker: PhantomData<C>,
+}
+
+/// Server implementation of [`StatePrediction`], advances `C` of all owned entities every frame.
+pub fn server_state_prediction_system<C: Component>(
+    prediction: Res<StatePrediction<C>>,
+    mut subjects: Query<&mut C, With<NetworkOwner>>,
+    time: Res<Time>,
+) {
+    for mut component in &mut subjects {
+        *component = (prediction.0)(&component, time.delta_secs());
+    }
+}
+
+/// Client implementation of [`StatePrediction`].
+///
+/// Runs after the rollback and replays the steps of all frames since the latest snapshot,
+/// including the current one.
+pub fn predicted_state_system<C: Component + Interpolate + Clone>(
+    prediction: Res<StatePrediction<C>>,
+    mut q_predicted: Query<
+        (
+            Entity,
+            &mut C,
+            &SnapshotBuffer<C>,
+            Option<&mut StatePredictionSteps<C>>,
+            Option<&mut PredictionState<C>>,
+            Option<&mut PredictionError<C>>,
+            Option<&Reconciled>,
+        ),
+        (
+            With<Predicted>,
+            Without<Interpolated>,
+            Without<PredictionPaused>,
+        ),
+    >,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    let delta_time = time.delta_secs();
+    for (entity, mut component, snapshot_buffer, steps, state, error, reconciled) in
+        &mut q_predicted
+    {
+        if snapshot_buffer.is_empty() {
+            continue;
+        }
+        let snapshot_tick = snapshot_buffer.latest_snapshot_tick();
+        let mut new_steps = StatePredictionSteps {
+            snapshot_tick,
+            deltas: Vec::new(),
+            marker: PhantomData,
+        };
+        let steps = match steps {
+            Some(steps) => steps.into_inner(),
+            None => &mut new_steps,
+        };
+        if steps.snapshot_tick != snapshot_tick {
+            steps.snapshot_tick = snapshot_tick;
+            steps.deltas.clear();
+        }
+        steps.deltas.push(delta_time);
+
+        if reconciled.is_none_or(|reconciled| reconciled.rolled_back) {
+            for delta in &steps.deltas {
+                *component = (prediction.0)(&component, *delta);
+            }
+        } else {
+            *component = (prediction.0)(&component, delta_time);
+        }
+        // The current step also continues the uncorrected prediction and the smoothing origin.
+        if let Some(mut state) = state {
+            state.uncorrected = (prediction.0)(&state.uncorrected, delta_time);
+        }
+        if let Some(mut error) = error {
+            error.from = (prediction.0)(&error.from, delta_time);
+        }
+        if !new_steps.deltas.is_empty() {
+            commands.entity(entity).insert(new_steps);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use bevy::{
+    ecs::{
+        system::{Query, Res, ResMut, Resource},
+        world::World,
+    },
+    time::Time,
+};
+use bevy_replicon::{
+    client::confirm_history::ConfirmHistory,
+    core::{replicon_client::RepliconClient, replicon_tick::RepliconTick},
+};
+use std::fmt::Debug;
+
+use crate::interpolation::SnapshotInterpolationConfig;
+
+/// Weight of a new round trip time sample in the smoothed estimate of [`EstimatedServerTick`].
+pub(super) const RTT_SMOOTHING: f32 = 0.1;
+
+/// Approximation of the tick the server is at right now on a client.
+///
+/// The latest received tick is RTT/2 stale when it arrives, so the estimate extrapolates it by the
+/// time since it was received and half of the smoothed round trip time reported by the transport.
+/// Predicted events are stamped with it, see [`PredictInfo::tick`](super::PredictInfo::tick).
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct EstimatedServerTick {
+    tick: RepliconTick,
+    /// Latest tick received from the server.
+    received: Option<RepliconTick>,
+    /// Seconds since `received` arrived.
+    since_received: f32,
+    rtt_secs: f32,
+}
+
+impl Default for EstimatedServerTick {
+    fn default() -> Self {
+        Self {
+            tick: RepliconTick::new(0),
+            received: None,
+            since_received: 0.0,
+            rtt_secs: 0.0,
+        }
+    }
+}
+
+impl EstimatedServerTick {
+    /// Estimated current server tick.
+    pub fn get(&self) -> RepliconTick {
+        self.tick
+    }
+
+    /// Latest tick received from the server, if any.
+    pub fn received(&self) -> Option<RepliconTick> {
+        self.received
+    }
+
+    /// Smoothed round trip time in seconds.
+    pub fn rtt_secs(&self) -> f32 {
+        self.rtt_secs
+    }
+}
+
+/// Updates [`EstimatedServerTick`] from the latest received tick and round trip time.
+pub fn estimate_server_tick_system(
+    mut estimated: ResMut<EstimatedServerTick>,
+    q_confirmed: Query<&ConfirmHistory>,
+    client: Res<RepliconClient>,
+    config: Res<SnapshotInterpolationConfig>,
+    time: Res<Time>,
+) {
+    let latest = q_confirmed
+        .iter()
+        .map(|confirmed| confirmed.last_tick())
+        .reduce(|latest, tick| if tick > latest { tick } else { latest });
+    let Some(latest) = latest.or(estimated.received) else {
+        return;
+    };
+
+    let rtt_secs = client.rtt() as f32;
+    if estimated.received.is_none() {
+        estimated.rtt_secs = rtt_secs;
+    } else {
+        estimated.rtt_secs += (rtt_secs - estimated.rtt_secs) * RTT_SMOOTHING;
+    }
+    if estimated.received.is_none_or(|received| latest > received) {
+        estimated.received = Some(latest);
+        estimated.since_received = 0.0;
+    } else {
+        estimated.since_received += time.delta_secs();
+    }
+
+    let elapsed_secs = estimated.since_received + estimated.rtt_secs / 2.0;
+    let elapsed_ticks = (elapsed_secs * config.max_tick_rate as f32).round() as u32;
+    estimated.tick = RepliconTick::new(latest.get().wrapping_add(elapsed_ticks));
+}
+
+pub(crate) fn reset_estimated_server_tick(world: &mut World) {
+    world.insert_resource(EstimatedServerTick::default());
+}
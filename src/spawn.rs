@@ -0,0 +1,266 @@
+use std::fmt::Debug;
+
+use bevy::{
+    app::{App, Update},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        event::{Event, EventReader, EventWriter},
+        query::{With, Without},
+        schedule::IntoSystemConfigs,
+        system::{Commands, EntityCommands, Query, Res, ResMut},
+    },
+    hierarchy::DespawnRecursiveExt,
+    time::{Time, Timer, TimerMode},
+};
+#[cfg(feature = "lod")]
+use bevy::{
+    ecs::{component::ComponentId, world::DeferredWorld},
+    render::view::Visibility,
+};
+use bevy_replicon::{
+    client::confirm_history::ConfirmHistory,
+    core::{channels::RepliconChannel, replicon_client::RepliconClient},
+    prelude::{
+        server_or_singleplayer, ClientEntityMap, ClientEventAppExt, ClientId, ClientMapping,
+        FromClient,
+    },
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Seconds a predicted entity waits for its server counterpart before it is despawned.
+pub const PREDICTED_SPAWN_TIMEOUT: f32 = 1.0;
+
+/// Seconds a predicted despawn waits for the server despawn before the entity is resurrected.
+pub const PREDICTED_DESPAWN_TIMEOUT: f32 = 1.0;
+
+/// Defines how an event spawns an entity.
+///
+/// The same hook is used to spawn the predicted entity on the client and the authoritative one
+/// on the server, so the spawned bundle should include `Replicated`.
+pub trait PredictSpawn: Event {
+    fn spawn(&self, commands: &mut Commands) -> Entity;
+}
+
+/// Sent to the server for every local [`PredictSpawn`] event.
+#[derive(Event, Serialize, Deserialize, Clone, Debug)]
+pub struct PredictedSpawnRequest<E: Event> {
+    pub event: E,
+    /// Bits of the entity the client already spawned, if any.
+    pub client_entity: Option<u64>,
+}
+
+/// A client entity spawned ahead of its server counterpart.
+///
+/// Removed once the server entity replicated onto it, the entity is despawned if that does not
+/// happen within [`PREDICTED_SPAWN_TIMEOUT`].
+#[derive(Component)]
+pub struct PredictedSpawn {
+    pub timeout: Timer,
+}
+
+impl PredictedSpawn {
+    pub fn new(timeout: f32) -> Self {
+        Self {
+            timeout: Timer::from_seconds(timeout, TimerMode::Once),
+        }
+    }
+}
+
+/// An entity the client predicted to be despawned, e.g. an exploding grenade.
+///
+/// On the server the entity is despawned right away. On clients it stays in limbo until the
+/// despawn replicates, gameplay and render queries should skip it with `Without<PredictedDespawn>`.
+/// If the server still has the entity after [`PREDICTED_DESPAWN_TIMEOUT`], the marker is removed.
+///
+/// With the `lod` feature the entity is also hidden in the meantime, its previous `Visibility` is
+/// restored if it is resurrected.
+#[derive(Component)]
+#[cfg_attr(feature = "lod", component(on_add = hide_despawned))]
+pub struct PredictedDespawn {
+    pub timeout: Timer,
+    #[cfg(feature = "lod")]
+    previous_visibility: Option<Visibility>,
+}
+
+impl PredictedDespawn {
+    pub fn new(timeout: f32) -> Self {
+        Self {
+            timeout: Timer::from_seconds(timeout, TimerMode::Once),
+            #[cfg(feature = "lod")]
+            previous_visibility: None,
+        }
+    }
+}
+
+#[cfg(feature = "lod")]
+fn hide_despawned(mut world: DeferredWorld, entity: Entity, _component_id: ComponentId) {
+    let Some(mut visibility) = world.get_mut::<Visibility>(entity) else {
+        return;
+    };
+    let previous = std::mem::replace(&mut *visibility, Visibility::Hidden);
+    if let Some(mut despawn) = world.get_mut::<PredictedDespawn>(entity) {
+        despawn.previous_visibility = Some(previous);
+    }
+}
+
+pub trait PredictedDespawnExt {
+    /// Despawns the entity on the server and marks it with [`PredictedDespawn`] on clients.
+    fn predicted_despawn(&mut self) -> &mut Self;
+}
+
+impl PredictedDespawnExt for EntityCommands<'_> {
+    fn predicted_despawn(&mut self) -> &mut Self {
+        self.insert(PredictedDespawn::new(PREDICTED_DESPAWN_TIMEOUT))
+    }
+}
+
+/// Spawns predicted entities for local events and requests their authoritative spawn.
+pub fn predicted_spawn_system<E: PredictSpawn + Clone>(
+    mut local_events: EventReader<E>,
+    mut requests: EventWriter<PredictedSpawnRequest<E>>,
+    client: Res<RepliconClient>,
+    mut commands: Commands,
+) {
+    for event in local_events.read() {
+        // Without a connection the server spawns the entity locally.
+        let client_entity = client.is_connected().then(|| {
+            let entity = event.spawn(&mut commands);
+            commands
+                .entity(entity)
+                .insert(PredictedSpawn::new(PREDICTED_SPAWN_TIMEOUT));
+            entity.to_bits()
+        });
+        requests.send(PredictedSpawnRequest {
+            event: event.clone(),
+            client_entity,
+        });
+    }
+}
+
+/// Spawns requested entities on the server and maps them to the predicted client entities.
+pub fn server_spawn_system<E: PredictSpawn>(
+    mut requests: EventReader<FromClient<PredictedSpawnRequest<E>>>,
+    mut entity_map: ResMut<ClientEntityMap>,
+    mut commands: Commands,
+) {
+    for FromClient { client_id, event } in requests.read() {
+        let server_entity = event.event.spawn(&mut commands);
+        if let Some(client_entity) = event
+            .client_entity
+            .filter(|_| *client_id != ClientId::SERVER)
+        {
+            entity_map.insert(
+                *client_id,
+                ClientMapping {
+                    server_entity,
+                    client_entity: Entity::from_bits(client_entity),
+                },
+            );
+        }
+    }
+}
+
+/// Confirms predicted entities the server replicated onto and despawns expired ones.
+pub fn confirm_predicted_spawns_system(
+    q_confirmed: Query<Entity, (With<PredictedSpawn>, With<ConfirmHistory>)>,
+    mut q_pending: Query<(Entity, &mut PredictedSpawn), Without<ConfirmHistory>>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for entity in q_confirmed.iter() {
+        commands.entity(entity).remove::<PredictedSpawn>();
+    }
+    for (entity, mut spawn) in q_pending.iter_mut() {
+        if spawn.timeout.tick(time.delta()).finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Despawns entities marked with [`PredictedDespawn`] on the server.
+pub fn server_despawn_system(
+    q_despawned: Query<Entity, With<PredictedDespawn>>,
+    mut commands: Commands,
+) {
+    for entity in q_despawned.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Resurrects entities the server did not despawn in time and restores their visibility.
+///
+/// Entities that were never replicated, e.g. predicted spawns, are despawned instead.
+pub fn predicted_despawn_timeout_system(
+    mut q_despawned: Query<(Entity, &mut PredictedDespawn, Option<&ConfirmHistory>)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (entity, mut despawn, confirmed) in q_despawned.iter_mut() {
+        if !despawn.timeout.tick(time.delta()).finished() {
+            continue;
+        }
+        if confirmed.is_some() {
+            let mut entity = commands.entity(entity);
+            entity.remove::<PredictedDespawn>();
+            #[cfg(feature = "lod")]
+            if let Some(visibility) = despawn.previous_visibility {
+                entity.insert(visibility);
+            }
+        } else {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+pub trait AppPredictedSpawnExt {
+    /// Register an event that spawns an entity through [`PredictSpawn`].
+    /// Clients spawn the entity immediately and reuse it once the server entity replicates.
+    fn add_predicted_spawn_event<E>(&mut self, channel: impl Into<RepliconChannel>) -> &mut Self
+    where
+        E: PredictSpawn + Serialize + DeserializeOwned + Debug + Clone;
+}
+
+impl AppPredictedSpawnExt for App {
+    fn add_predicted_spawn_event<E>(&mut self, channel: impl Into<RepliconChannel>) -> &mut Self
+    where
+        E: PredictSpawn + Serialize + DeserializeOwned + Debug + Clone,
+    {
+        self.add_event::<E>()
+            .add_client_event::<PredictedSpawnRequest<E>>(channel)
+            .add_systems(
+                Update,
+                (
+                    predicted_spawn_system::<E>,
+                    server_spawn_system::<E>.run_if(server_or_singleplayer), // Runs only on the server or a single player.
+                ),
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::{schedule::Schedule, world::World};
+    use bevy_replicon::core::replicon_tick::RepliconTick;
+
+    use super::*;
+
+    #[test]
+    fn resurrects_entities_the_server_kept() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        let entity = world.spawn(ConfirmHistory::new(RepliconTick::new(0))).id();
+        #[cfg(feature = "lod")]
+        world.entity_mut(entity).insert(Visibility::Visible);
+        world.entity_mut(entity).insert(PredictedDespawn::new(0.0));
+        #[cfg(feature = "lod")]
+        assert_eq!(world.get::<Visibility>(entity), Some(&Visibility::Hidden));
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(predicted_despawn_timeout_system);
+        schedule.run(&mut world);
+        assert!(!world.entity(entity).contains::<PredictedDespawn>());
+        #[cfg(feature = "lod")]
+        assert_eq!(world.get::<Visibility>(entity), Some(&Visibility::Visible));
+    }
+}
@@ -0,0 +1,83 @@
+use bevy::math::Vec2;
+use bevy_replicon_snap::interpolation::Interpolate;
+use bevy_replicon_snap_macros::Interpolate;
+
+#[derive(Interpolate, Clone, Debug, PartialEq)]
+struct Generic<T: Clone>
+where
+    T: Copy,
+{
+    value: T,
+}
+
+#[test]
+fn generic_struct() {
+    let a = Generic { value: Vec2::ZERO };
+    let b = Generic {
+        value: Vec2::new(2.0, 4.0),
+    };
+    assert_eq!(
+        a.interpolate(b, 0.5),
+        Generic {
+            value: Vec2::new(1.0, 2.0)
+        }
+    );
+}
+
+#[derive(Interpolate, Clone)]
+struct Offset {
+    x: f32,
+    y: f32,
+    #[interpolate(skip)]
+    id: u8,
+}
+
+#[test]
+fn distance_combines_interpolated_fields() {
+    let a = Offset {
+        x: 0.0,
+        y: 0.0,
+        id: 1,
+    };
+    let b = Offset {
+        x: 3.0,
+        y: 4.0,
+        id: 2,
+    };
+    assert_eq!(a.distance(&b), 5.0);
+}
+
+#[derive(Interpolate, Clone, Debug, PartialEq)]
+struct Pos {
+    x: f32,
+    y: f32,
+}
+
+#[test]
+fn named_struct_midpoint() {
+    let a = Pos { x: 0.0, y: 10.0 };
+    let b = Pos { x: 4.0, y: 20.0 };
+    assert_eq!(a.interpolate(b, 0.5), Pos { x: 2.0, y: 15.0 });
+}
+
+#[derive(Interpolate, Clone, Debug, PartialEq)]
+struct One(f32);
+
+#[derive(Interpolate, Clone, Debug, PartialEq)]
+struct Two(f32, Vec2);
+
+#[derive(Interpolate, Clone, Debug, PartialEq)]
+struct Three(f32, f32, f32);
+
+#[test]
+fn tuple_structs_interpolate_every_field() {
+    assert_eq!(One(0.0).interpolate(One(2.0), 0.5), One(1.0));
+    assert_eq!(
+        Two(0.0, Vec2::ZERO).interpolate(Two(2.0, Vec2::new(4.0, 6.0)), 0.5),
+        Two(1.0, Vec2::new(2.0, 3.0))
+    );
+    assert_eq!(
+        Three(0.0, 10.0, -2.0).interpolate(Three(2.0, 20.0, 2.0), 0.5),
+        Three(1.0, 15.0, 0.0)
+    );
+}
@@ -0,0 +1,715 @@
+use std::{collections::VecDeque, time::Duration};
+
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+use bevy_replicon::{
+    core::server_entity_map::ServerEntityMap, prelude::*, server::server_tick::ServerTick,
+    test_app::ServerTestAppExt,
+};
+use bevy_replicon_snap::{
+    interpolation::{AppInterpolationExt, Interpolated, RecordSnapshotsMarker, SnapshotBuffer},
+    prediction::{
+        remote_prediction_system, AppPredictionExt, LocalClientId, OwnerPredicted,
+        OwnershipChanged, Predict, PredictEventSpawn, PredictInfo, PredictSimple, Predicted,
+        PredictedEventHistory, PredictionCorrected, PredictionDivergenceLog, Reconciled,
+        RemoteInput,
+    },
+    NetworkOwner, SnapshotInterpolationPlugin,
+};
+use bevy_replicon_snap_macros::Interpolate;
+use serde::{Deserialize, Serialize};
+
+#[derive(Event, Serialize, Deserialize, Clone, Debug)]
+struct Move(f32);
+
+#[derive(Component, Interpolate, Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Position(f32);
+
+#[derive(Component, Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Stamina(f32);
+
+impl Predict<Move, Stamina> for Position {
+    fn apply_event(&mut self, event: &Move, info: &PredictInfo, stamina: &mut Stamina) {
+        self.0 += event.0 * info.delta_time;
+        stamina.0 -= 1.0;
+    }
+}
+
+/// Server tick rate, the apps tick every frame.
+const TICK_RATE: u16 = 100;
+const FRAME: Duration = Duration::from_millis(10);
+
+fn app() -> App {
+    app_with_tick_policy(TickPolicy::EveryFrame)
+}
+
+fn app_with_tick_policy(tick_policy: TickPolicy) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        RepliconPlugins.set(ServerPlugin {
+            tick_policy,
+            ..Default::default()
+        }),
+        SnapshotInterpolationPlugin::new(TICK_RATE),
+    ))
+    .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME))
+    .replicate_interpolated::<Position>()
+    .add_client_predicted_event::<Move>(ChannelKind::Ordered)
+    .predict_event_for_component::<Move, Stamina, Position>();
+    app
+}
+
+/// Server and client app with a connected client.
+fn connected() -> (App, App) {
+    let mut server_app = app();
+    let mut client_app = app();
+    server_app.connect_client(&mut client_app);
+    (server_app, client_app)
+}
+
+fn client_id(client_app: &App) -> ClientId {
+    client_app
+        .world()
+        .resource::<RepliconClient>()
+        .id()
+        .expect("client should be connected")
+}
+
+/// Runs a server tick and delivers its messages to the client.
+fn exchange(server_app: &mut App, client_app: &mut App) {
+    let client_id = client_id(client_app);
+    exchange_with_id(server_app, client_app, client_id);
+}
+
+/// Same as [`exchange`], for clients whose transport does not report their id.
+fn exchange_with_id(server_app: &mut App, client_app: &mut App, client_id: ClientId) {
+    server_app.update();
+    deliver(server_app, client_app, client_id);
+    client_app.update();
+    // Inputs sent during the client update reach the server before its next tick
+    deliver(server_app, client_app, client_id);
+}
+
+/// Moves all pending messages between the apps, the server only has this client.
+fn deliver(server_app: &mut App, client_app: &mut App, client_id: ClientId) {
+    let mut client = client_app.world_mut().resource_mut::<RepliconClient>();
+    let mut server = server_app.world_mut().resource_mut::<RepliconServer>();
+    for (channel_id, message) in client.drain_sent() {
+        server.insert_received(client_id, channel_id, message);
+    }
+    for (_, channel_id, message) in server.drain_sent() {
+        client.insert_received(channel_id, message);
+    }
+}
+
+fn spawn_player(server_app: &mut App, owner: ClientId) -> Entity {
+    server_app
+        .world_mut()
+        .spawn((
+            Position(0.0),
+            Stamina(100.0),
+            NetworkOwner(owner.get()),
+            OwnerPredicted,
+            Replicated,
+        ))
+        .id()
+}
+
+/// Client entity replicated from the server entity `server_entity`.
+fn client_entity(client_app: &mut App, server_entity: Entity) -> Entity {
+    let entity_map = client_app.world().resource::<ServerEntityMap>();
+    *entity_map
+        .to_client()
+        .get(&server_entity)
+        .expect("entity should be replicated")
+}
+
+#[test]
+fn remote_prediction_keeps_replicated_context() {
+    let mut world = World::new();
+    world.insert_resource(Time::<()>::default());
+    let mut buffer = SnapshotBuffer::new();
+    buffer.insert(Position(0.0), 1);
+    let entity = world
+        .spawn((
+            Position(0.0),
+            Stamina(10.0),
+            buffer,
+            RemoteInput {
+                event: Some(Move(1.0)),
+            },
+            Interpolated,
+        ))
+        .id();
+    let mut schedule = Schedule::default();
+    schedule.add_systems(remote_prediction_system::<Move, Stamina, Position>);
+
+    for _ in 0..3 {
+        schedule.run(&mut world);
+    }
+
+    assert_eq!(world.get::<Stamina>(entity), Some(&Stamina(10.0)));
+}
+
+#[test]
+fn ownership_flip_swaps_markers() {
+    let (mut server_app, mut client_app) = connected();
+    let server_entity = spawn_player(&mut server_app, client_id(&client_app));
+    exchange(&mut server_app, &mut client_app);
+    let entity = client_entity(&mut client_app, server_entity);
+    assert!(client_app.world().get::<Predicted>(entity).is_some());
+    assert!(client_app.world().get::<Interpolated>(entity).is_none());
+
+    let other_client = ClientId::new(client_id(&client_app).get() + 1);
+    server_app
+        .world_mut()
+        .entity_mut(server_entity)
+        .insert(NetworkOwner(other_client.get()));
+    exchange(&mut server_app, &mut client_app);
+    assert!(client_app.world().get::<Predicted>(entity).is_none());
+    assert!(client_app.world().get::<Interpolated>(entity).is_some());
+
+    server_app
+        .world_mut()
+        .entity_mut(server_entity)
+        .insert(NetworkOwner(client_id(&client_app).get()));
+    exchange(&mut server_app, &mut client_app);
+    assert!(client_app.world().get::<Predicted>(entity).is_some());
+    assert!(client_app.world().get::<Interpolated>(entity).is_none());
+
+    let changes: Vec<bool> = client_app
+        .world_mut()
+        .resource_mut::<Events<OwnershipChanged>>()
+        .drain()
+        .map(|change| change.predicted)
+        .collect();
+    assert_eq!(changes, [false, true]);
+}
+
+#[test]
+fn ownership_uses_local_client_id_without_transport_id() {
+    let mut server_app = app();
+    let mut client_app = app();
+    server_app.connect_client(&mut client_app);
+    // No messaging backend reports an id, e.g. a custom transport
+    let client_id = client_id(&client_app);
+    client_app
+        .world_mut()
+        .resource_mut::<RepliconClient>()
+        .set_status(RepliconClientStatus::Connected { client_id: None });
+    client_app.insert_resource(LocalClientId(client_id));
+
+    let server_entity = spawn_player(&mut server_app, client_id);
+    let other_entity = spawn_player(&mut server_app, ClientId::new(client_id.get() + 1));
+    exchange_with_id(&mut server_app, &mut client_app, client_id);
+    let entity = client_entity(&mut client_app, server_entity);
+    let other_entity = client_entity(&mut client_app, other_entity);
+    assert!(client_app.world().get::<Predicted>(entity).is_some());
+    assert!(client_app
+        .world()
+        .get::<Interpolated>(other_entity)
+        .is_some());
+}
+
+#[test]
+fn join_under_latency_waits_for_first_snapshot() {
+    let (mut server_app, mut client_app) = connected();
+    let server_entity = spawn_player(&mut server_app, client_id(&client_app));
+    exchange(&mut server_app, &mut client_app);
+    let entity = client_entity(&mut client_app, server_entity);
+    assert!(client_app.world().get::<Predicted>(entity).is_some());
+    assert!(client_app
+        .world()
+        .get::<SnapshotBuffer<Position>>(entity)
+        .is_none());
+    // Also covers buffers created before their first snapshot, e.g. by a hook
+    client_app
+        .world_mut()
+        .entity_mut(entity)
+        .insert(SnapshotBuffer::<Position>::new());
+
+    // No snapshot arrives while the server updates are delayed
+    for _ in 0..5 {
+        client_app.world_mut().send_event(Move(1.0));
+        client_app.update();
+    }
+    assert_eq!(
+        client_app
+            .world()
+            .resource::<PredictedEventHistory<Move>>()
+            .len(),
+        5
+    );
+
+    // Another component confirms a newer tick before the first position arrives
+    server_app
+        .world_mut()
+        .get_mut::<Stamina>(server_entity)
+        .unwrap()
+        .0 = 50.0;
+    exchange(&mut server_app, &mut client_app);
+    assert!(client_app
+        .world()
+        .get::<SnapshotBuffer<Position>>(entity)
+        .is_some_and(SnapshotBuffer::is_empty));
+
+    for _ in 0..3 {
+        exchange(&mut server_app, &mut client_app);
+    }
+    let server_position = server_app.world().get::<Position>(server_entity).unwrap();
+    assert!(server_position.0 > 0.0);
+    assert!(!client_app
+        .world()
+        .get::<SnapshotBuffer<Position>>(entity)
+        .expect("snapshots should be recorded once the server moved the entity")
+        .is_empty());
+    assert_eq!(
+        client_app.world().get::<Position>(entity),
+        Some(server_position)
+    );
+}
+
+#[test]
+fn dedicated_server_never_records_snapshots() {
+    let (mut server_app, mut client_app) = connected();
+    let server_entity = server_app
+        .world_mut()
+        .spawn((Position(0.0), Interpolated, Replicated))
+        .id();
+    for step in 1..=10 {
+        server_app
+            .world_mut()
+            .get_mut::<Position>(server_entity)
+            .unwrap()
+            .0 = step as f32;
+        exchange(&mut server_app, &mut client_app);
+    }
+
+    let server_entity = server_app.world().entity(server_entity);
+    assert!(!server_entity.contains::<RecordSnapshotsMarker>());
+    assert!(!server_entity.contains::<SnapshotBuffer<Position>>());
+    let entity = client_entity(&mut client_app, server_entity.id());
+    assert!(!client_app
+        .world()
+        .get::<SnapshotBuffer<Position>>(entity)
+        .expect("the client should record snapshots")
+        .is_empty());
+}
+
+#[test]
+fn single_player_applies_predict_path() {
+    let mut app = app();
+    let entity = app
+        .world_mut()
+        .spawn((
+            Position(0.0),
+            Stamina(100.0),
+            NetworkOwner(ClientId::SERVER.get()),
+            OwnerPredicted,
+        ))
+        .id();
+    for _ in 0..5 {
+        app.world_mut().send_event(Move(2.0));
+        app.update();
+    }
+    // The local loopback delivers inputs in the next frame
+    app.update();
+
+    // Identical to applying the recorded inputs directly
+    let mut expected = (Position(0.0), Stamina(100.0));
+    let history = app.world().resource::<PredictedEventHistory<Move>>();
+    assert_eq!(history.len(), 5);
+    for event_snapshot in history.iter() {
+        let info = PredictInfo {
+            tick: event_snapshot.tick,
+            delta_time: event_snapshot.delta_time,
+            is_resimulation: false,
+            sequence: event_snapshot.sequence,
+        };
+        let (position, stamina) = &mut expected;
+        Predict::<Move, Stamina>::apply_event(position, &event_snapshot.value, &info, stamina);
+    }
+    assert!(app.world().get::<Predicted>(entity).is_some());
+    assert_eq!(app.world().get::<Position>(entity), Some(&expected.0));
+    assert_eq!(app.world().get::<Stamina>(entity), Some(&expected.1));
+}
+
+#[test]
+fn server_applies_inputs_on_tick() {
+    let mut server_app = app_with_tick_policy(TickPolicy::Manual);
+    let mut client_app = app();
+    server_app.connect_client(&mut client_app);
+    let client_id = client_id(&client_app);
+    let server_entity = spawn_player(&mut server_app, client_id);
+    server_app
+        .world_mut()
+        .resource_mut::<ServerTick>()
+        .increment();
+    exchange(&mut server_app, &mut client_app);
+
+    // Frames between ticks only queue the received inputs
+    for _ in 0..3 {
+        client_app.world_mut().send_event(Move(1.0));
+        client_app.update();
+        deliver(&mut server_app, &mut client_app, client_id);
+        server_app.update();
+        assert_eq!(
+            server_app.world().get::<Position>(server_entity),
+            Some(&Position(0.0))
+        );
+        assert!(server_app
+            .world()
+            .resource::<Events<FromClient<Move>>>()
+            .is_empty());
+    }
+
+    server_app
+        .world_mut()
+        .resource_mut::<ServerTick>()
+        .increment();
+    server_app.update();
+    assert_eq!(
+        server_app.world().get::<Stamina>(server_entity),
+        Some(&Stamina(97.0))
+    );
+    assert_eq!(
+        server_app
+            .world()
+            .resource::<Events<FromClient<Move>>>()
+            .len(),
+        3
+    );
+}
+
+#[test]
+fn predicts_without_confirm_history() {
+    let (_server_app, mut client_app) = connected();
+    let mut snapshot_buffer = SnapshotBuffer::new();
+    snapshot_buffer.insert(Position(0.0), 0);
+    let entity = client_app
+        .world_mut()
+        .spawn((Position(0.0), Stamina(100.0), snapshot_buffer, Predicted))
+        .id();
+
+    for _ in 0..5 {
+        client_app.world_mut().send_event(Move(1.0));
+        client_app.update();
+    }
+
+    let history = client_app.world().resource::<PredictedEventHistory<Move>>();
+    assert_eq!(history.len(), 5);
+    let recorded: f32 = history.iter().map(|e| e.value.0 * e.delta_time).sum();
+    assert!(recorded > 0.0);
+    let position = client_app.world().get::<Position>(entity).unwrap();
+    assert!((position.0 - recorded).abs() < 1e-6);
+    assert_eq!(
+        client_app.world().get::<Stamina>(entity),
+        Some(&Stamina(95.0))
+    );
+}
+
+#[derive(Event, Serialize, Deserialize, Clone, Debug)]
+struct Drain;
+
+#[derive(Event, Serialize, Deserialize, Clone, Debug)]
+struct Refill;
+
+#[derive(Component, Interpolate, Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Energy(f32);
+
+impl PredictSimple<Drain> for Energy {
+    fn apply_event(&mut self, _event: &Drain, _info: &PredictInfo) {
+        self.0 /= 2.0;
+    }
+}
+
+impl PredictSimple<Refill> for Energy {
+    fn apply_event(&mut self, _event: &Refill, _info: &PredictInfo) {
+        self.0 += 10.0;
+    }
+}
+
+#[test]
+fn event_types_replay_in_recording_order() {
+    let mut server_app = app();
+    let mut client_app = app();
+    for app in [&mut server_app, &mut client_app] {
+        app.replicate_interpolated::<Energy>()
+            .add_client_predicted_event::<Drain>(ChannelKind::Ordered)
+            .add_client_predicted_event::<Refill>(ChannelKind::Ordered)
+            .predict_event_for_component_simple::<Drain, Energy>()
+            .predict_event_for_component_simple::<Refill, Energy>();
+    }
+    server_app.connect_client(&mut client_app);
+    let server_entity = spawn_player(&mut server_app, client_id(&client_app));
+    server_app
+        .world_mut()
+        .entity_mut(server_entity)
+        .insert(Energy(0.0));
+    // Snapshots are recorded from the update after the entity became predicted
+    exchange(&mut server_app, &mut client_app);
+    exchange(&mut server_app, &mut client_app);
+    server_app
+        .world_mut()
+        .get_mut::<Energy>(server_entity)
+        .unwrap()
+        .0 = 20.0;
+    exchange(&mut server_app, &mut client_app);
+    let entity = client_entity(&mut client_app, server_entity);
+
+    // Registered after `Drain`, but recorded first
+    client_app.world_mut().send_event(Refill);
+    client_app.update();
+    client_app.world_mut().send_event(Drain);
+    client_app.update();
+    assert_eq!(
+        client_app.world().get::<Energy>(entity),
+        Some(&Energy(15.0))
+    );
+
+    // A new snapshot of another component rolls back and replays both inputs
+    server_app
+        .world_mut()
+        .get_mut::<Stamina>(server_entity)
+        .unwrap()
+        .0 = 50.0;
+    server_app.update();
+    let client_id = client_id(&client_app);
+    deliver(&mut server_app, &mut client_app, client_id);
+    client_app.update();
+    assert_eq!(
+        client_app
+            .world()
+            .get::<Reconciled>(entity)
+            .map(|reconciled| reconciled.rolled_back),
+        Some(true)
+    );
+    assert_eq!(
+        client_app.world().get::<Energy>(entity),
+        Some(&Energy(15.0))
+    );
+}
+
+#[derive(Component, Interpolate, Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Facing(f32);
+
+impl PredictSimple<Move> for Facing {
+    fn apply_event(&mut self, event: &Move, _info: &PredictInfo) {
+        self.0 = event.0.signum();
+    }
+}
+
+/// Moves the position without changing the facing.
+#[derive(Event, Serialize, Deserialize, Clone, Debug)]
+struct Push;
+
+impl PredictSimple<Push> for Position {
+    fn apply_event(&mut self, _event: &Push, info: &PredictInfo) {
+        self.0 += 0.5 * info.delta_time;
+    }
+}
+
+#[test]
+fn components_of_one_event_reconcile_at_own_cadence() {
+    let mut server_app = app();
+    let mut client_app = app();
+    for app in [&mut server_app, &mut client_app] {
+        app.replicate_interpolated::<Facing>()
+            .predict_event_for_component_simple::<Move, Facing>()
+            .add_client_predicted_event::<Push>(ChannelKind::Ordered)
+            .predict_event_for_component_simple::<Push, Position>();
+    }
+    server_app.connect_client(&mut client_app);
+    let client_id = client_id(&client_app);
+    let server_entity = spawn_player(&mut server_app, client_id);
+    server_app
+        .world_mut()
+        .entity_mut(server_entity)
+        .insert(Facing(1.0));
+    // Snapshots are recorded from the update after the entity became predicted
+    exchange(&mut server_app, &mut client_app);
+    exchange(&mut server_app, &mut client_app);
+    server_app
+        .world_mut()
+        .get_mut::<Position>(server_entity)
+        .unwrap()
+        .0 = 1.0;
+    server_app
+        .world_mut()
+        .get_mut::<Facing>(server_entity)
+        .unwrap()
+        .0 = -1.0;
+    exchange(&mut server_app, &mut client_app);
+    let entity = client_entity(&mut client_app, server_entity);
+    // Corrections of the server side changes above
+    client_app
+        .world_mut()
+        .resource_mut::<Events<PredictionCorrected<Position>>>()
+        .clear();
+    client_app
+        .world_mut()
+        .resource_mut::<Events<PredictionCorrected<Facing>>>()
+        .clear();
+
+    // Inputs reach the server 3 frames late, so snapshots arrive while inputs are in flight.
+    // `Position` receives a snapshot every tick, `Facing` only around ticks with a `Move`.
+    let mut in_flight = VecDeque::new();
+    let mut corrections = 0;
+    let mut cadences_differ = false;
+    for frame in 0..20 {
+        if frame < 16 {
+            client_app.world_mut().send_event(Push);
+        }
+        if frame % 5 == 0 {
+            let direction = if frame % 10 == 0 { 1.0 } else { -1.0 };
+            client_app.world_mut().send_event(Move(direction));
+        }
+        client_app.update();
+        let sent: Vec<_> = client_app
+            .world_mut()
+            .resource_mut::<RepliconClient>()
+            .drain_sent()
+            .collect();
+        in_flight.push_back(sent);
+        if in_flight.len() > 3 {
+            let mut server = server_app.world_mut().resource_mut::<RepliconServer>();
+            for (channel_id, message) in in_flight.pop_front().unwrap() {
+                server.insert_received(client_id, channel_id, message);
+            }
+        }
+        server_app.update();
+        deliver(&mut server_app, &mut client_app, client_id);
+
+        let client_world = client_app.world_mut();
+        corrections += client_world
+            .resource_mut::<Events<PredictionCorrected<Position>>>()
+            .drain()
+            .count();
+        corrections += client_world
+            .resource_mut::<Events<PredictionCorrected<Facing>>>()
+            .drain()
+            .count();
+        let entity = client_world.entity(entity);
+        let position_tick = entity
+            .get::<SnapshotBuffer<Position>>()
+            .unwrap()
+            .latest_snapshot_tick();
+        let facing_tick = entity
+            .get::<SnapshotBuffer<Facing>>()
+            .unwrap()
+            .latest_snapshot_tick();
+        cadences_differ |= position_tick != facing_tick;
+    }
+    for _ in 0..4 {
+        exchange(&mut server_app, &mut client_app);
+    }
+
+    assert!(cadences_differ);
+    assert_eq!(corrections, 0);
+    assert_eq!(
+        client_app.world().get::<Facing>(entity),
+        server_app.world().get::<Facing>(server_entity)
+    );
+    assert_eq!(
+        client_app.world().get::<Position>(entity),
+        server_app.world().get::<Position>(server_entity)
+    );
+}
+
+/// Distance covered, leaves a `Trail` behind every `Move`.
+#[derive(Component, Interpolate, Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Odometer(f32);
+
+impl Predict<Move, Stamina> for Odometer {
+    fn apply_event(&mut self, event: &Move, info: &PredictInfo, _stamina: &mut Stamina) {
+        self.0 += event.0.abs() * info.delta_time;
+    }
+}
+
+impl PredictEventSpawn<Move, Stamina> for Odometer {
+    type Spawn = Trail;
+
+    fn spawn_for_event(
+        &self,
+        _event: &Move,
+        _info: &PredictInfo,
+        _stamina: &Stamina,
+    ) -> Option<Trail> {
+        Some(Trail(self.0))
+    }
+}
+
+#[derive(Component)]
+struct Trail(f32);
+
+/// Client side `Position`, `Odometer`, `Stamina` and trails after every frame of inputs in flight.
+fn reconciled_values(divergence_log: bool) -> Vec<(f32, f32, f32, Vec<f32>)> {
+    let mut server_app = app();
+    let mut client_app = app();
+    for app in [&mut server_app, &mut client_app] {
+        app.replicate_interpolated::<Odometer>()
+            .predict_event_for_component_with_spawn::<Move, Stamina, Odometer>()
+            .add_client_predicted_event::<Push>(ChannelKind::Ordered)
+            .predict_event_for_component_simple::<Push, Position>();
+    }
+    if divergence_log {
+        client_app.insert_resource(PredictionDivergenceLog);
+    }
+    server_app.connect_client(&mut client_app);
+    let client_id = client_id(&client_app);
+    let server_entity = spawn_player(&mut server_app, client_id);
+    server_app
+        .world_mut()
+        .entity_mut(server_entity)
+        .insert(Odometer(0.0));
+    // Snapshots are recorded from the update after the entity became predicted
+    exchange(&mut server_app, &mut client_app);
+    exchange(&mut server_app, &mut client_app);
+    let entity = client_entity(&mut client_app, server_entity);
+
+    // Inputs reach the server 3 frames late, so divergence checks replay acknowledged moves
+    // while newer ones are still in flight.
+    let mut in_flight = VecDeque::new();
+    let mut values = Vec::new();
+    for frame in 0..20 {
+        client_app.world_mut().send_event(Push);
+        if frame % 5 == 0 {
+            client_app.world_mut().send_event(Move(1.0));
+        }
+        client_app.update();
+        let sent: Vec<_> = client_app
+            .world_mut()
+            .resource_mut::<RepliconClient>()
+            .drain_sent()
+            .collect();
+        in_flight.push_back(sent);
+        if in_flight.len() > 3 {
+            let mut server = server_app.world_mut().resource_mut::<RepliconServer>();
+            for (channel_id, message) in in_flight.pop_front().unwrap() {
+                server.insert_received(client_id, channel_id, message);
+            }
+        }
+        server_app.update();
+        deliver(&mut server_app, &mut client_app, client_id);
+
+        let client_world = client_app.world_mut();
+        let trails = client_world
+            .query::<&Trail>()
+            .iter(client_world)
+            .map(|trail| trail.0)
+            .collect();
+        let entity = client_world.entity(entity);
+        values.push((
+            entity.get::<Position>().unwrap().0,
+            entity.get::<Odometer>().unwrap().0,
+            entity.get::<Stamina>().unwrap().0,
+            trails,
+        ));
+    }
+    values
+}
+
+#[test]
+fn divergence_log_keeps_reconciled_values() {
+    assert_eq!(reconciled_values(true), reconciled_values(false));
+}